@@ -1,32 +1,107 @@
-use crate::cli::Config;
-use reqwest::Client;
+use crate::{cli::Config, client::RedditClient, error::ShredditError};
 use serde::Deserialize;
 use serde_json::Value;
-use std::collections::HashMap;
-
-pub async fn new_access_token(args: &Config, client: &Client) -> Result<String, String> {
-    let params = HashMap::from([
-        ("grant_type", "password"),
-        ("username", &args.username),
-        ("password", &args.password),
-    ]);
-
-    let res: AccessTokenResponse = client
-        .post("https://www.reddit.com/api/v1/access_token")
-        .form(&params)
-        .basic_auth(&args.client_id, Some(&args.client_secret))
-        .header("User-Agent", args.user_agent.clone())
-        .send()
-        .await
-        .unwrap()
-        .json()
-        .await
-        .unwrap();
-
-    match res {
-        AccessTokenResponse::Success { access_token } => Ok(access_token),
-        AccessTokenResponse::Error { message, .. } => Err(message),
-        AccessTokenResponse::Unexpected(json) => Err(serde_json::to_string(&json).unwrap()),
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+use tokio::{sync::Mutex, time::Instant};
+use tracing::debug;
+
+/// Refresh tokens this long before Reddit says they expire, so in-flight requests don't race the expiry.
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Hands out access tokens, fetching a new one whenever the current one is about to expire.
+/// Clones share the same token.
+#[derive(Clone)]
+pub struct TokenProvider {
+    username: String,
+    password: String,
+    client_id: String,
+    client_secret: String,
+    auth_url: String,
+    token: Arc<Mutex<Option<AccessToken>>>,
+}
+
+struct AccessToken {
+    value: String,
+    expires_at: Instant,
+}
+
+impl fmt::Debug for TokenProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenProvider")
+            .field("username", &self.username)
+            .field("client_id", &self.client_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenProvider {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            username: config.username.clone(),
+            password: config.password.clone(),
+            client_id: config.client_id.clone(),
+            client_secret: config.client_secret.clone(),
+            auth_url: config.auth_url.trim_end_matches('/').to_string(),
+            token: Arc::default(),
+        }
+    }
+
+    /// Returns a token that's valid for at least [`REFRESH_MARGIN`].
+    pub async fn token(&self, client: &RedditClient) -> Result<String, ShredditError> {
+        let mut token = self.token.lock().await;
+
+        if let Some(token) = token.as_ref()
+            && token.expires_at > Instant::now() + REFRESH_MARGIN
+        {
+            return Ok(token.value.clone());
+        }
+
+        debug!("Fetching a new access token...");
+        let new_token = self.new_access_token(client).await?;
+        let value = new_token.value.clone();
+        *token = Some(new_token);
+
+        Ok(value)
+    }
+
+    /// Forgets a token Reddit rejected, so the next call to [`Self::token`] fetches a new one.
+    pub async fn invalidate(&self, rejected: &str) {
+        let mut token = self.token.lock().await;
+
+        if token.as_ref().is_some_and(|t| t.value == rejected) {
+            *token = None;
+        }
+    }
+
+    async fn new_access_token(&self, client: &RedditClient) -> Result<AccessToken, ShredditError> {
+        let params = HashMap::from([
+            ("grant_type", "password"),
+            ("username", &self.username),
+            ("password", &self.password),
+        ]);
+
+        let url = format!("{}/api/v1/access_token", self.auth_url);
+
+        let res: AccessTokenResponse = client
+            .send_json_unauthenticated(
+                client
+                    .post(url)
+                    .form(&params)
+                    .basic_auth(&self.client_id, Some(&self.client_secret)),
+            )
+            .await?;
+
+        match res {
+            AccessTokenResponse::Success {
+                access_token,
+                expires_in,
+            } => Ok(AccessToken {
+                value: access_token,
+                expires_at: Instant::now() + Duration::from_secs(expires_in),
+            }),
+            AccessTokenResponse::Error { message, .. } => Err(ShredditError::Auth(message)),
+            AccessTokenResponse::Unexpected(json) => Err(ShredditError::Unexpected(json)),
+        }
     }
 }
 
@@ -35,6 +110,7 @@ pub async fn new_access_token(args: &Config, client: &Client) -> Result<String,
 enum AccessTokenResponse {
     Success {
         access_token: String,
+        expires_in: u64,
     },
     Error {
         #[allow(dead_code)]
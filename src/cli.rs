@@ -1,15 +1,24 @@
-use crate::things::{CommentIdSet, LOREM_IPSUM, PostIdSet, SubredditSet, ThingType};
+use crate::{
+    replacement::{PhraseOrder, Phrases, Placeholders, ReplacementStrategy, Replacer},
+    things::{CommentIdSet, LOREM_IPSUM, PostIdSet, SubredditSet, ThingType, UserSet},
+};
 use clap::Parser;
-use jiff::Zoned;
+use jiff::{SignedDuration, Zoned};
 use parse_datetime::parse_datetime;
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use tracing::{debug, warn};
 
 /// Parses relative timestamps to support:
 /// - Absolute timestamps (ISO 8601) → `2025-01-31T03:16:30Z`
 /// - [Negative durations](https://github.com/uutils/parse_datetime) (`-30 days`, `-2 weeks`, `-5 hours`)
 fn parse_relative(input: &str) -> Result<Zoned, String> {
-    let datetime = parse_datetime(input).map_err(|e| format!("invalid datetime {e}"))?;
+    let datetime = parse_datetime(input)
+        .map_err(|e| format!("invalid datetime {e}"))?
+        .into_zoned()
+        .ok_or_else(|| "datetime is out of range".to_string())?;
 
     if datetime > Zoned::now() {
         return Err("relative datetimes must be before current time. please use either negative relative format (`-30 days`) or an absolute timestamp in the past".to_string());
@@ -18,6 +27,25 @@ fn parse_relative(input: &str) -> Result<Zoned, String> {
     Ok(datetime)
 }
 
+/// Parses durations like `500ms`, `30s` or `2 minutes`.
+fn parse_duration(input: &str) -> Result<Duration, String> {
+    let duration: SignedDuration = input.parse().map_err(|e| format!("invalid duration {e}"))?;
+
+    duration
+        .try_into()
+        .map_err(|_| "durations must not be negative".to_string())
+}
+
+/// Loads the phrases of a replacement file.
+fn parse_phrases(input: &str) -> Result<Phrases, String> {
+    Phrases::load(Path::new(input))
+}
+
+const DEFAULT_USER_AGENT: &str = "ShredditRustClient";
+const DEFAULT_API_URL: &str = "https://oauth.reddit.com";
+const DEFAULT_AUTH_URL: &str = "https://www.reddit.com";
+const DEFAULT_EDITED_FILE: &str = "shreddit-edited.csv";
+
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
 pub struct Config {
@@ -59,28 +87,119 @@ pub struct Config {
     #[clap(long, env = "SHREDDIT_MAX_SCORE")]
     pub max_score: Option<i64>,
 
-    /// Allows a user to specify a custom string as their comment replacement text
+    /// Allows a user to specify a custom string as their comment replacement text. It can contain
+    /// `{id}`, `{subreddit}`, `{created}`, `{permalink}` and `{now}`, which are filled in for every edit
     #[clap(short, long, env = "SHREDDIT_REPLACEMENT_COMMENT", default_value = LOREM_IPSUM, allow_hyphen_values = true)]
     pub replacement_comment: String,
 
+    /// How to come up with the text comments and self posts are overwritten with. Random text is
+    /// generated fresh for every edit.
+    #[clap(
+        long,
+        env = "SHREDDIT_REPLACEMENT_STRATEGY",
+        value_enum,
+        default_value_t
+    )]
+    pub replacement_strategy: ReplacementStrategy,
+
+    /// Seeds the random replacement text, so it's the same on every run.
+    #[clap(long, env = "SHREDDIT_REPLACEMENT_SEED")]
+    pub replacement_seed: Option<u64>,
+
+    /// A file of phrases to overwrite comments and self posts with instead of the replacement
+    /// comment. A `.json` file is read as an array of strings, any other file as a phrase per line.
+    #[clap(long, env = "SHREDDIT_REPLACEMENT_FILE", value_parser = parse_phrases)]
+    pub replacement_file: Option<Phrases>,
+
+    /// In which order edits use the phrases of the replacement file.
+    #[clap(long, env = "SHREDDIT_REPLACEMENT_ORDER", value_enum, default_value_t)]
+    pub replacement_order: PhraseOrder,
+
+    /// How many times to overwrite comments and self posts before deleting them. Every pass but
+    /// the last overwrites them with random text, so archivers that snapshot edits don't just see
+    /// the same text again.
+    #[clap(long, env = "SHREDDIT_OVERWRITE_PASSES", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub overwrite_passes: u32,
+
+    /// How long to wait between overwrite passes (e.g., `5s`).
+    #[clap(long, env = "SHREDDIT_OVERWRITE_DELAY", value_parser = parse_duration)]
+    pub overwrite_delay: Option<Duration>,
+
     /// The User-Agent for Reddit API requests.
     #[clap(
         long,
         env = "SHREDDIT_USER_AGENT",
-        default_value = "ShredditRustClient"
+        default_value = DEFAULT_USER_AGENT
     )]
     pub user_agent: String,
 
-    /// The path of the directory of the unzipped GDPR export data.
-    /// If set, `shreddit` will use the GDPR export folder instead of
+    /// The base URL of Reddit's OAuth API.
+    #[clap(
+        long,
+        env = "SHREDDIT_API_URL",
+        default_value = DEFAULT_API_URL
+    )]
+    pub api_url: String,
+
+    /// The base URL to request access tokens from.
+    #[clap(
+        long,
+        env = "SHREDDIT_AUTH_URL",
+        default_value = DEFAULT_AUTH_URL
+    )]
+    pub auth_url: String,
+
+    /// How long to wait for Reddit to respond to a request before giving up on that attempt (e.g., `30s`).
+    #[clap(long, env = "SHREDDIT_REQUEST_TIMEOUT", default_value = "30s", value_parser = parse_duration)]
+    pub request_timeout: Duration,
+
+    /// How many times to try a Reddit API request before giving up on it. Requests are retried
+    /// when they time out, get rate limited or hit a Reddit server error.
+    #[clap(long, env = "SHREDDIT_MAX_ATTEMPTS", default_value_t = 5)]
+    pub max_attempts: u32,
+
+    /// How long to wait before retrying a failed request (e.g., `500ms`, `2s`). Doubles with every retry.
+    #[clap(long, env = "SHREDDIT_RETRY_DELAY", default_value = "1s", value_parser = parse_duration)]
+    pub retry_delay: Duration,
+
+    /// The longest to wait between retries of a failed request (e.g., `1m`).
+    #[clap(long, env = "SHREDDIT_MAX_RETRY_DELAY", default_value = "1m", value_parser = parse_duration)]
+    pub max_retry_delay: Duration,
+
+    /// The path of the GDPR export, either the `.zip` Reddit sent or the directory it was extracted to.
+    /// If set, `shreddit` will use the GDPR export instead of
     /// Reddit's APIs for discovering your data.
     #[clap(long, env = "SHREDDIT_GDPR_EXPORT_DIR")]
     pub gdpr_export_dir: Option<PathBuf>,
 
-    /// If specified, comments will only be edited, not deleted. - Requires gdpr_export
+    /// If set, stop as soon as a row of the GDPR export can't be read, e.g. because Reddit changed
+    /// its columns. Otherwise such rows are skipped and summarized at the end.
+    #[clap(long, env = "SHREDDIT_GDPR_STRICT")]
+    pub gdpr_strict: bool,
+
+    /// If specified, comments and self posts will only be edited, not deleted. - Requires gdpr_export
     #[clap(long, env = "SHREDDIT_EDIT_ONLY")]
     pub edit_only: bool,
 
+    /// Two-phase mode: only overwrite comments and self posts and record them in the edited file,
+    /// then delete them in a later run once they've been overwritten for this long (e.g., `48h`).
+    /// This gives scrapers time to pick up the overwritten text. Things with no text to overwrite
+    /// are deleted right away.
+    #[clap(long, env = "SHREDDIT_DELETE_EDITED_AFTER", value_parser = parse_duration)]
+    pub delete_edited_after: Option<Duration>,
+
+    /// Where two-phase mode records the things it overwrote.
+    #[clap(long, env = "SHREDDIT_EDITED_FILE", default_value = DEFAULT_EDITED_FILE)]
+    pub edited_file: PathBuf,
+
+    /// If specified, gilded comments and posts will be kept.
+    #[clap(long, env = "SHREDDIT_KEEP_GILDED")]
+    pub keep_gilded: bool,
+
+    /// If specified, comments and posts distinguished as a moderator or admin will be kept.
+    #[clap(long, env = "SHREDDIT_KEEP_DISTINGUISHED")]
+    pub keep_distinguished: bool,
+
     /// If specified, will skip these subreddits. Comma-separated. - Case-sensitive
     #[clap(long, env = "SHREDDIT_SKIP_SUBREDDITS")]
     pub skip_subreddits: Option<SubredditSet>,
@@ -93,6 +212,14 @@ pub struct Config {
     #[clap(long, env = "SHREDDIT_SKIP_POST_IDS")]
     pub skip_post_ids: Option<PostIdSet>,
 
+    /// If specified, will skip messages sent from or to these users. - Comma-separated
+    #[clap(long, env = "SHREDDIT_SKIP_MESSAGE_USERS")]
+    pub skip_message_users: Option<UserSet>,
+
+    /// If specified, will keep these friends. - Comma-separated
+    #[clap(long, env = "SHREDDIT_SKIP_FRIENDS")]
+    pub skip_friends: Option<UserSet>,
+
     /// If specified, only posts, comments, saved posts, and saved comments in the specified subreddits will be deleted. - Comma-separated. Case-sensitive
     #[clap(
         long,
@@ -103,6 +230,101 @@ pub struct Config {
 }
 
 impl Config {
+    /// A config with the same defaults as the CLI, for using `shreddit` as a library.
+    pub fn new(
+        username: impl Into<String>,
+        password: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            dry_run: false,
+            thing_types: vec![ThingType::Posts, ThingType::Comments],
+            before: None,
+            after: None,
+            max_score: None,
+            replacement_comment: LOREM_IPSUM.to_string(),
+            replacement_strategy: ReplacementStrategy::Fixed,
+            replacement_seed: None,
+            replacement_file: None,
+            replacement_order: PhraseOrder::Random,
+            overwrite_passes: 1,
+            overwrite_delay: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            api_url: DEFAULT_API_URL.to_string(),
+            auth_url: DEFAULT_AUTH_URL.to_string(),
+            request_timeout: Duration::from_secs(30),
+            max_attempts: 5,
+            retry_delay: Duration::from_secs(1),
+            max_retry_delay: Duration::from_secs(60),
+            gdpr_export_dir: None,
+            gdpr_strict: false,
+            edit_only: false,
+            delete_edited_after: None,
+            edited_file: PathBuf::from(DEFAULT_EDITED_FILE),
+            keep_gilded: false,
+            keep_distinguished: false,
+            skip_subreddits: None,
+            skip_comment_ids: None,
+            skip_post_ids: None,
+            skip_message_users: None,
+            skip_friends: None,
+            only_subreddits: None,
+        }
+    }
+
+    /// The text to overwrite `original` with, following the replacement strategy. The
+    /// replacement comment's placeholders are filled in from `placeholders`.
+    pub fn replacement(
+        &self,
+        replacer: &Replacer,
+        original: &str,
+        placeholders: &Placeholders,
+    ) -> String {
+        let template = match &self.replacement_file {
+            Some(phrases) => replacer.phrase(phrases, self.replacement_order),
+            None => &self.replacement_comment,
+        };
+
+        replacer.text(
+            self.replacement_strategy,
+            &placeholders.render(template),
+            original,
+        )
+    }
+
+    /// The texts to overwrite `original` with, one per overwrite pass. The last one is
+    /// [`Self::replacement`], the ones before it are random text of the same length.
+    pub fn replacements(
+        &self,
+        replacer: &Replacer,
+        original: &str,
+        placeholders: &Placeholders,
+    ) -> Vec<String> {
+        let passes = self.overwrite_passes.max(1) as usize;
+
+        let mut texts: Vec<String> = (1..passes)
+            .map(|_| replacer.text(ReplacementStrategy::MatchLength, "", original))
+            .collect();
+        texts.push(self.replacement(replacer, original, placeholders));
+
+        texts
+    }
+
+    /// Whether `text` is what a thing would be overwritten with, other than random text.
+    pub fn is_replacement(&self, text: &str, placeholders: &Placeholders) -> bool {
+        let mut templates: Box<dyn Iterator<Item = &str>> = match &self.replacement_file {
+            Some(phrases) => Box::new(phrases.iter()),
+            None => Box::new([self.replacement_comment.as_str()].into_iter()),
+        };
+
+        templates.any(|template| placeholders.matches(template, text))
+    }
+
     /// Return TRUE if either edit_only or dr_run
     pub fn should_prevent_deletion(&self) -> bool {
         if self.edit_only {
@@ -147,4 +369,35 @@ mod tests {
         let relative_future = parse_relative("30 days").unwrap_err();
         assert!(dbg!(relative_future).contains("must be before current time"));
     }
+
+    #[test]
+    fn duration() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("1m").unwrap(), Duration::from_secs(60));
+        assert_eq!(
+            parse_duration("2 hours").unwrap(),
+            Duration::from_secs(7200)
+        );
+        assert!(parse_duration("-1s").is_err());
+    }
+
+    #[test]
+    fn new_matches_cli_defaults() {
+        let parsed = Config::parse_from([
+            "shreddit",
+            "--username",
+            "user",
+            "--password",
+            "pass",
+            "--client-id",
+            "id",
+            "--client-secret",
+            "secret",
+        ]);
+
+        assert_eq!(
+            format!("{:?}", Config::new("user", "pass", "id", "secret")),
+            format!("{parsed:?}")
+        );
+    }
 }
@@ -0,0 +1,367 @@
+use crate::{
+    access_token::TokenProvider, cli::Config, error::ShredditError, rate_limit::RateLimiter,
+    retry::RetryPolicy,
+};
+use async_stream::stream;
+use futures_core::Stream;
+use reqwest::{Client, IntoUrl, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::time::sleep;
+use tracing::{debug, error, warn};
+
+/// A client for Reddit's OAuth API. Requests are authenticated with a fresh access token, share one
+/// rate limit budget and are retried on transient failures.
+#[derive(Debug, Clone)]
+pub struct RedditClient {
+    http: Client,
+    base_url: String,
+    rate_limiter: RateLimiter,
+    retry_policy: RetryPolicy,
+    tokens: TokenProvider,
+}
+
+impl RedditClient {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            http: Client::builder()
+                .user_agent(&config.user_agent)
+                .timeout(config.request_timeout)
+                .build()
+                .expect("TLS backend should be available"),
+            base_url: config.api_url.trim_end_matches('/').to_string(),
+            rate_limiter: RateLimiter::default(),
+            retry_policy: RetryPolicy::from(config),
+            tokens: TokenProvider::new(config),
+        }
+    }
+
+    /// https://www.reddit.com/dev/api/#POST_api_del
+    pub async fn del(&self, fullname: &str) -> Result<(), ShredditError> {
+        let params = HashMap::from([("id", fullname)]);
+
+        self.send(self.post(self.url("/api/del")).form(&params))
+            .await?;
+
+        Ok(())
+    }
+
+    /// https://www.reddit.com/dev/api/#POST_api_del_msg
+    pub async fn del_msg(&self, fullname: &str) -> Result<(), ShredditError> {
+        let params = HashMap::from([("id", fullname)]);
+
+        self.send(self.post(self.url("/api/del_msg")).form(&params))
+            .await?;
+
+        Ok(())
+    }
+
+    /// https://www.reddit.com/dev/api/#POST_api_editusertext
+    pub async fn edit_usertext(&self, fullname: &str, text: &str) -> Result<(), ShredditError> {
+        #[allow(unused)]
+        #[derive(Debug, Deserialize)]
+        #[serde(untagged)]
+        enum EditResponse {
+            Success { jquery: Vec<Value>, success: bool },
+            Unexpected(Value),
+        }
+
+        let params = HashMap::from([("thing_id", fullname), ("text", text)]);
+        let max_attempts = self.retry_policy.max_attempts;
+        let mut attempt = 1;
+
+        loop {
+            let res: EditResponse = self
+                .send_json(
+                    self.post(self.url("/api/editusertext"))
+                        .query(&[("raw_json", "1")])
+                        .form(&params),
+                )
+                .await?;
+
+            let res = match res {
+                EditResponse::Success { jquery, .. } => match ShredditError::from_jquery(&jquery) {
+                    Some(e) => Err(e),
+                    None => Ok(()),
+                },
+                EditResponse::Unexpected(v) => Err(ShredditError::Unexpected(v)),
+            };
+
+            // Reddit reports rate limited edits in the response body rather than with a 429, so
+            // `send` can't retry them.
+            match res {
+                Err(ShredditError::RateLimited) if attempt < max_attempts => {
+                    self.rate_limiter.exhaust();
+                    let delay = self.retry_policy.backoff(attempt);
+                    warn!("Edit {attempt}/{max_attempts} was rate limited, retrying in {delay:?}");
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// https://www.reddit.com/dev/api/#POST_api_unsave
+    pub async fn unsave(&self, fullname: &str) -> Result<(), ShredditError> {
+        let params = HashMap::from([("id", fullname)]);
+
+        self.send(self.post(self.url("/api/unsave")).form(&params))
+            .await?;
+
+        Ok(())
+    }
+
+    /// https://www.reddit.com/dev/api/#POST_api_unhide
+    pub async fn unhide(&self, fullname: &str) -> Result<(), ShredditError> {
+        let params = HashMap::from([("id", fullname)]);
+
+        self.send(self.post(self.url("/api/unhide")).form(&params))
+            .await?;
+
+        Ok(())
+    }
+
+    /// https://www.reddit.com/dev/api/#POST_api_vote
+    pub async fn clear_vote(&self, fullname: &str) -> Result<(), ShredditError> {
+        let params = HashMap::from([("id", fullname), ("dir", "0")]);
+
+        self.send(self.post(self.url("/api/vote")).form(&params))
+            .await?;
+
+        Ok(())
+    }
+
+    /// https://www.reddit.com/dev/api/#POST_api_subscribe
+    pub async fn unsubscribe(&self, subreddit: &str) -> Result<(), ShredditError> {
+        let params = HashMap::from([("action", "unsub"), ("sr_name", subreddit)]);
+
+        self.send(self.post(self.url("/api/subscribe")).form(&params))
+            .await?;
+
+        Ok(())
+    }
+
+    /// https://www.reddit.com/dev/api/#DELETE_api_v1_me_friends_{username}
+    pub async fn unfriend(&self, username: &str) -> Result<(), ShredditError> {
+        let url = self.url(&format!("/api/v1/me/friends/{username}"));
+
+        self.send(self.delete(url)).await?;
+
+        Ok(())
+    }
+
+    /// https://www.reddit.com/dev/api/#GET_api_v1_me_friends
+    pub async fn friends<T: DeserializeOwned>(&self) -> Result<Vec<T>, ShredditError> {
+        #[derive(Debug, Deserialize)]
+        struct UserList<T> {
+            data: UserListData<T>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct UserListData<T> {
+            children: Vec<T>,
+        }
+
+        let list: UserList<T> = self
+            .send_json(
+                self.get(self.url("/api/v1/me/friends"))
+                    .query(&[("raw_json", "1")]),
+            )
+            .await?;
+
+        Ok(list.data.children)
+    }
+
+    /// https://www.reddit.com/dev/api/#GET_api_info
+    pub async fn info<T: DeserializeOwned>(
+        &self,
+        fullnames: &[String],
+    ) -> Result<Vec<T>, ShredditError> {
+        let ids = fullnames.join(",");
+
+        let listing: Listing<T> = self
+            .send_json(
+                self.get(self.url("/api/info"))
+                    .query(&[("id", ids.as_str()), ("raw_json", "1")]),
+            )
+            .await?;
+
+        Ok(listing.into_things())
+    }
+
+    /// Pages through a listing such as `/user/{username}/comments`, following its `after` cursor
+    /// until Reddit runs out of results.
+    pub fn listing<T: DeserializeOwned>(&self, path: &str) -> impl Stream<Item = T> + use<T> {
+        let client = self.clone();
+        let url = self.url(path);
+
+        stream! {
+            let mut after: Option<String> = None;
+
+            loop {
+                let mut request = client.get(&url).query(&[("limit", "100"), ("raw_json", "1")]);
+                if let Some(after) = &after {
+                    request = request.query(&[("after", after)]);
+                }
+
+                let listing: Listing<T> = match client.send_json(request).await {
+                    Ok(listing) => listing,
+                    Err(e) => {
+                        error!("Failed to fetch {url}: {e}");
+                        break;
+                    }
+                };
+
+                debug!("Page contained {} results", listing.data.children.len());
+
+                after = listing.data.after.clone();
+
+                for thing in listing.into_things() {
+                    yield thing;
+                }
+
+                if after.is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    /// Returns the current access token, fetching one if needed.
+    pub async fn access_token(&self) -> Result<String, ShredditError> {
+        self.tokens.token(self).await
+    }
+
+    pub fn get(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.http.get(url)
+    }
+
+    pub fn post(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.http.post(url)
+    }
+
+    pub fn delete(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.http.delete(url)
+    }
+
+    /// Sends a request authenticated with the current access token. If Reddit rejects the token,
+    /// e.g. because it expired early, it's refreshed and the request is sent once more.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, ShredditError> {
+        let retry = request.try_clone();
+        let token = self.access_token().await?;
+
+        let res = self.send_unauthenticated(request.bearer_auth(&token)).await;
+
+        match (res, retry) {
+            (Err(ShredditError::Status { status, .. }), Some(retry))
+                if status == StatusCode::UNAUTHORIZED =>
+            {
+                warn!("Access token was rejected, fetching a new one");
+                self.tokens.invalidate(&token).await;
+                let token = self.access_token().await?;
+
+                self.send_unauthenticated(retry.bearer_auth(token)).await
+            }
+            (res, _) => res,
+        }
+    }
+
+    /// Sends a request once the rate limiter allows it, turning transport failures and
+    /// non-success statuses into [`ShredditError`]s. Transient failures are retried according
+    /// to the [`RetryPolicy`].
+    pub async fn send_unauthenticated(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Response, ShredditError> {
+        let max_attempts = self.retry_policy.max_attempts;
+        let mut attempt = 1;
+
+        loop {
+            // Requests with streaming bodies can't be cloned, so they only get one attempt.
+            let Some(retry) = request.try_clone().filter(|_| attempt < max_attempts) else {
+                return self.send_once(request).await;
+            };
+
+            match self.send_once(retry).await {
+                Err(e) if e.is_transient() => {
+                    let delay = self.retry_policy.backoff(attempt);
+                    warn!("Attempt {attempt}/{max_attempts} failed ({e}), retrying in {delay:?}");
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    async fn send_once(&self, request: RequestBuilder) -> Result<Response, ShredditError> {
+        self.rate_limiter.acquire().await;
+
+        let res = request.send().await?;
+        let status = res.status();
+
+        self.rate_limiter.update(res.headers());
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            self.rate_limiter.exhaust();
+            return Err(ShredditError::RateLimited);
+        }
+
+        if !status.is_success() {
+            let body = res.text().await.unwrap_or_default();
+            return Err(ShredditError::Status { status, body });
+        }
+
+        Ok(res)
+    }
+
+    /// Sends an authenticated request and deserializes its JSON response body.
+    pub async fn send_json<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, ShredditError> {
+        decode(self.send(request).await?).await
+    }
+
+    /// Sends a request without an access token and deserializes its JSON response body.
+    pub async fn send_json_unauthenticated<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, ShredditError> {
+        decode(self.send_unauthenticated(request).await?).await
+    }
+}
+
+/// https://www.reddit.com/dev/api/#listings
+#[derive(Debug, Deserialize)]
+struct Listing<T> {
+    data: ListingData<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListingData<T> {
+    children: Vec<Child<T>>,
+    after: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Child<T> {
+    data: T,
+}
+
+impl<T> Listing<T> {
+    fn into_things(self) -> Vec<T> {
+        self.data.children.into_iter().map(|c| c.data).collect()
+    }
+}
+
+async fn decode<T: DeserializeOwned>(res: Response) -> Result<T, ShredditError> {
+    let body = res.text().await?;
+    serde_json::from_str(&body).map_err(|error| ShredditError::Decode { error, body })
+}
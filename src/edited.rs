@@ -0,0 +1,155 @@
+//! The log of things overwritten in two-phase mode (`--delete-edited-after`), which later runs
+//! delete once the things have been overwritten for long enough.
+
+use crate::things::ThingType;
+use csv::{ReaderBuilder, WriterBuilder};
+use jiff::{SignedDuration, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tracing::warn;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    fullname: String,
+    edited_at: Timestamp,
+}
+
+#[derive(Debug)]
+pub struct EditedLog {
+    path: PathBuf,
+    /// When each thing was overwritten, by fullname.
+    entries: HashMap<String, Timestamp>,
+}
+
+impl EditedLog {
+    /// Reads the log at `path`, which doesn't have to exist yet. Rows that can't be read are
+    /// skipped with a warning.
+    pub fn load(path: &Path) -> csv::Result<Self> {
+        let contents = match fs::read(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    path: path.to_path_buf(),
+                    entries: HashMap::new(),
+                });
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let entries = ReaderBuilder::new()
+            .from_reader(contents.as_slice())
+            .into_deserialize()
+            .filter_map(|entry| {
+                entry
+                    .inspect_err(|e| warn!("Skipping row of {}: {e}", path.display()))
+                    .ok()
+            })
+            .map(
+                |Entry {
+                     fullname,
+                     edited_at,
+                 }| (fullname, edited_at),
+            )
+            .collect();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn contains(&self, fullname: &str) -> bool {
+        self.entries.contains_key(fullname)
+    }
+
+    /// The things that were overwritten at least `grace` before `now`, oldest first.
+    pub fn due(&self, grace: Duration, now: Timestamp) -> Vec<String> {
+        let grace = SignedDuration::try_from(grace).unwrap_or(SignedDuration::MAX);
+
+        self.entries()
+            .filter(|entry| entry.edited_at.duration_until(now) >= grace)
+            .map(|entry| entry.fullname)
+            .collect()
+    }
+
+    /// Records that `fullname` was just overwritten, appending it to the file.
+    pub fn record(&mut self, fullname: &str) -> csv::Result<()> {
+        let entry = Entry {
+            fullname: fullname.to_string(),
+            edited_at: Timestamp::now(),
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let is_new = file.metadata()?.len() == 0;
+
+        let mut writer = WriterBuilder::new().has_headers(is_new).from_writer(file);
+        writer.serialize(&entry)?;
+        writer.flush()?;
+
+        self.entries.insert(entry.fullname, entry.edited_at);
+
+        Ok(())
+    }
+
+    /// Forgets a deleted thing. The file isn't rewritten until [`save`](Self::save).
+    pub fn remove(&mut self, fullname: &str) {
+        self.entries.remove(fullname);
+    }
+
+    /// Rewrites the file with the things that are left. They're written to a temporary file
+    /// first, so an interrupted run can't leave the log half written.
+    pub fn save(&self) -> csv::Result<()> {
+        let tmp = self.path.with_extension("csv.tmp");
+
+        let mut writer = WriterBuilder::new().from_path(&tmp)?;
+        if self.entries.is_empty() {
+            writer.write_record(["fullname", "edited_at"])?;
+        }
+        for entry in self.entries() {
+            writer.serialize(entry)?;
+        }
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(&tmp, &self.path)?;
+
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The entries in the order they were overwritten.
+    fn entries(&self) -> impl Iterator<Item = Entry> {
+        let mut entries: Vec<Entry> = self
+            .entries
+            .iter()
+            .map(|(fullname, &edited_at)| Entry {
+                fullname: fullname.clone(),
+                edited_at,
+            })
+            .collect();
+        entries.sort_by(|a, b| (a.edited_at, &a.fullname).cmp(&(b.edited_at, &b.fullname)));
+
+        entries.into_iter()
+    }
+}
+
+/// Only comments and self posts are overwritten, so only they end up in the log.
+pub fn thing_type(fullname: &str) -> ThingType {
+    if fullname.starts_with("t3_") {
+        ThingType::Posts
+    } else {
+        ThingType::Comments
+    }
+}
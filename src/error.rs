@@ -0,0 +1,137 @@
+use crate::sources::gdpr::MalformedRow;
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::{fmt, path::PathBuf};
+
+#[derive(Debug)]
+pub enum ShredditError {
+    /// The request never got a response, e.g. because of a connection reset or timeout.
+    Transport(reqwest::Error),
+    /// Reddit responded with a non-success status code.
+    Status { status: StatusCode, body: String },
+    /// The response body wasn't the JSON we expected.
+    Decode {
+        error: serde_json::Error,
+        body: String,
+    },
+    /// The response was valid JSON, but not in a shape we know how to handle.
+    Unexpected(Value),
+    /// Reddit accepted the request but reported errors in its `jquery` response.
+    Reddit(Vec<String>),
+    /// Reddit asked us to slow down.
+    RateLimited,
+    /// Reddit refused to issue an access token.
+    Auth(String),
+    /// The thing couldn't be found on Reddit.
+    NotFound(String),
+    /// A row of the GDPR export couldn't be read.
+    MalformedRow(MalformedRow),
+    /// The log of things overwritten in two-phase mode couldn't be read.
+    EditedLog { path: PathBuf, error: csv::Error },
+}
+
+impl fmt::Display for ShredditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(e) => write!(f, "request failed: {e}"),
+            Self::Status { status, body } => write!(f, "Reddit responded with {status}: {body}"),
+            Self::Decode { error, body } => {
+                write!(f, "couldn't decode Reddit's response ({error}): {body}")
+            }
+            Self::Unexpected(v) => write!(f, "unexpected response from Reddit: {v}"),
+            Self::Reddit(errors) => write!(f, "Reddit reported errors: {}", errors.join(", ")),
+            Self::RateLimited => write!(f, "rate limited by Reddit"),
+            Self::Auth(message) => write!(f, "couldn't get an access token: {message}"),
+            Self::NotFound(fullname) => write!(f, "{fullname} not found"),
+            Self::MalformedRow(row) => {
+                write!(
+                    f,
+                    "couldn't read {} line {}: {}",
+                    row.file, row.line, row.message
+                )
+            }
+            Self::EditedLog { path, error } => {
+                write!(f, "couldn't read {}: {error}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShredditError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(e) => Some(e),
+            Self::Decode { error, .. } => Some(error),
+            Self::EditedLog { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ShredditError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Transport(e)
+    }
+}
+
+impl ShredditError {
+    /// Whether the request might succeed if we try again later.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Transport(_) | Self::RateLimited => true,
+            Self::Status { status, .. } => status.is_server_error(),
+            _ => false,
+        }
+    }
+
+    /// Reddit reports failures of `api/*` form endpoints as a `jquery` array of calls rather
+    /// than an error status. Each error is a call with an argument like `.error.RATELIMIT.field-ratelimit`.
+    pub fn from_jquery(jquery: &[Value]) -> Option<Self> {
+        let errors: Vec<String> = jquery
+            .iter()
+            .filter_map(Value::as_array)
+            .flatten()
+            .filter_map(Value::as_array)
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter_map(|s| s.strip_prefix(".error."))
+            .map(|s| s.split('.').next().unwrap_or(s).to_string())
+            .collect();
+
+        if errors.iter().any(|e| e == "RATELIMIT") {
+            Some(Self::RateLimited)
+        } else if errors.is_empty() {
+            None
+        } else {
+            Some(Self::Reddit(errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn jquery_errors() {
+        let ok = json!([[0, 1, "call", ["body"]], [1, 2, "attr", "find"]]);
+        assert!(ShredditError::from_jquery(ok.as_array().unwrap()).is_none());
+
+        let rate_limited = json!([
+            [0, 1, "call", ["body"]],
+            [10, 11, "call", [".error.RATELIMIT.field-ratelimit"]],
+            [11, 12, "call", ["you are doing that too much"]]
+        ]);
+        assert!(matches!(
+            ShredditError::from_jquery(rate_limited.as_array().unwrap()),
+            Some(ShredditError::RateLimited)
+        ));
+
+        let too_old = json!([[10, 11, "call", [".error.TOO_OLD.field-thing_id"]]]);
+        match ShredditError::from_jquery(too_old.as_array().unwrap()) {
+            Some(ShredditError::Reddit(errors)) => assert_eq!(errors, ["TOO_OLD"]),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Overwrite and delete your Reddit account history.
+//!
+//! ```no_run
+//! use futures_util::{StreamExt, pin_mut};
+//! use shreddit::{Shredder, ThingType};
+//!
+//! # async fn example() -> Result<(), shreddit::ShredditError> {
+//! let shredder = Shredder::builder("username", "password", "client-id", "client-secret")
+//!     .thing_types([ThingType::Comments])
+//!     .skip_subreddits(["rust"])
+//!     .dry_run(true)
+//!     .build();
+//!
+//! shredder.authenticate().await?;
+//!
+//! let events = shredder.run()?;
+//! pin_mut!(events);
+//!
+//! while let Some(event) = events.next().await {
+//!     println!("{}: {:?}", event.name, event.outcome);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod access_token;
+pub mod cli;
+pub mod client;
+pub mod edited;
+pub mod error;
+pub mod rate_limit;
+pub mod replacement;
+pub mod retry;
+pub mod shredder;
+pub mod sources;
+pub mod things;
+
+pub use cli::Config;
+pub use error::ShredditError;
+pub use replacement::{PhraseOrder, ReplacementStrategy};
+pub use shredder::{Event, Shredder, ShredderBuilder};
+pub use things::{Outcome, ThingType};
@@ -1,27 +1,11 @@
-use std::error::Error;
+use std::{collections::BTreeMap, error::Error};
 
-use access_token::new_access_token;
 use clap::Parser;
-use cli::Config;
 use futures_util::{StreamExt, pin_mut};
-use reqwest::Client;
-use things::Shred;
-use tracing::{debug, error, info};
+use shreddit::{Config, Event, Outcome, Shredder, ShredditError};
+use tracing::{debug, error, info, warn};
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::{
-    sources::gdpr,
-    things::{
-        Comment, Friend, Post, SavedComment, SavedPost, ThingType, comment, post, saved_comment,
-        saved_post,
-    },
-};
-
-mod access_token;
-mod cli;
-mod sources;
-mod things;
-
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), Box<dyn Error>> {
     let config_file = dotenvy::from_filename("shreddit.env").ok();
@@ -37,125 +21,68 @@ async fn main() -> Result<(), Box<dyn Error>> {
         None => debug!("No shreddit.env config file found."),
     }
 
-    let client = Client::new();
-    let access_token = match new_access_token(&config, &client).await {
-        Ok(token) => token,
+    let shredder = Shredder::new(config);
+    if let Err(e) = shredder.authenticate().await {
+        error!("{e}");
+        return Err(e.into());
+    }
+
+    let events = match shredder.run() {
+        Ok(events) => events,
         Err(e) => {
             error!("{e}");
             return Err(e.into());
         }
     };
+    pin_mut!(events);
 
-    match &config.gdpr_export_dir {
-        Some(export_path) => {
-            for thing_type in config.thing_types.iter() {
-                info!("Shredding {thing_type:?}...");
-
-                match thing_type {
-                    ThingType::Comments => {
-                        let comments = gdpr::list::<Comment>(export_path);
-
-                        for comment in comments {
-                            comment.shred(&client, &access_token, &config).await;
-                        }
-                    }
-
-                    ThingType::Friends => {
-                        let friends = gdpr::list::<Friend>(export_path);
-
-                        for friend in friends {
-                            friend.shred(&client, &access_token, &config).await;
-                        }
-                    }
-
-                    ThingType::Posts => {
-                        let posts = gdpr::list::<Post>(export_path);
-
-                        for post in posts {
-                            post.shred(&client, &access_token, &config).await;
-                        }
-                    }
-
-                    ThingType::SavedPosts => {
-                        let saved_posts = gdpr::list::<SavedPost>(export_path);
-
-                        for saved_post in saved_posts {
-                            saved_post.shred(&client, &access_token, &config).await;
-                        }
-                    }
-
-                    ThingType::SavedComments => {
-                        let saved_comments = gdpr::list::<SavedComment>(export_path);
+    // Malformed rows of the GDPR export by file, and files that were skipped as a whole.
+    let mut skipped_rows: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut skipped_files: BTreeMap<&str, String> = BTreeMap::new();
 
-                        for saved_comment in saved_comments {
-                            saved_comment.shred(&client, &access_token, &config).await;
-                        }
-                    }
-                }
+    while let Some(event) = events.next().await {
+        log_event(&event);
 
-                info!("Completed shredding {thing_type:?}");
+        if let Err(ShredditError::MalformedRow(row)) = &event.outcome {
+            if row.skipped_file {
+                skipped_files.insert(row.file, row.message.clone());
+            } else {
+                *skipped_rows.entry(row.file).or_default() += 1;
             }
 
-            info!("Completed shredding {:?}", config.thing_types);
-        }
-        None => {
-            for thing_type in config.thing_types.iter() {
-                info!("Shredding {thing_type:?}...");
-
-                match thing_type {
-                    ThingType::Posts => {
-                        let posts = post::list(&client, &access_token, &config).await;
-                        pin_mut!(posts);
-
-                        while let Some(post) = posts.next().await {
-                            post.shred(&client, &access_token, &config).await;
-                        }
-                    }
-
-                    ThingType::Comments => {
-                        let comments = comment::list(&client, &access_token, &config).await;
-                        pin_mut!(comments);
-
-                        while let Some(comment) = comments.next().await {
-                            comment.shred(&client, &access_token, &config).await;
-                        }
-                    }
-
-                    ThingType::Friends => {
-                        error!("Shredding friends based on API is a TODO");
-                        todo!();
-                    }
-
-                    ThingType::SavedPosts => {
-                        let saved_posts = saved_post::list(&client, &access_token, &config).await;
-                        pin_mut!(saved_posts);
-
-                        while let Some(saved_post) = saved_posts.next().await {
-                            saved_post.shred(&client, &access_token, &config).await;
-                        }
-                    }
-
-                    ThingType::SavedComments => {
-                        let saved_comments =
-                            saved_comment::list(&client, &access_token, &config).await;
-                        pin_mut!(saved_comments);
-
-                        while let Some(saved_comment) = saved_comments.next().await {
-                            saved_comment.shred(&client, &access_token, &config).await;
-                        }
-                    }
-                }
-
-                info!("Completed shredding {thing_type:?}");
+            if shredder.config().gdpr_strict {
+                error!("Stopping because of `--gdpr-strict`");
+                return Err(event.outcome.unwrap_err().into());
             }
-
-            info!("Completed shredding {:?}", config.thing_types);
         }
-    };
+    }
+
+    for (file, message) in skipped_files {
+        warn!("Skipped {file}: {message}");
+    }
+    for (file, count) in skipped_rows {
+        warn!("Skipped {count} malformed row(s) of {file}");
+    }
+
+    info!("Completed shredding {:?}", shredder.config().thing_types);
 
     Ok(())
 }
 
+fn log_event(Event { name, outcome, .. }: &Event) {
+    match outcome {
+        Ok(Outcome::Skipped) => debug!("Skipped {name}"),
+        Ok(Outcome::DryRun) => info!("Would have shredded {name}"),
+        Ok(Outcome::Edited) => info!("Edited {name}"),
+        Ok(Outcome::Deleted) => info!("Deleted {name}"),
+        Err(ShredditError::MalformedRow(row)) if row.skipped_file => {
+            warn!("Skipping {}: {}", row.file, row.message)
+        }
+        Err(e @ ShredditError::MalformedRow(_)) => warn!("Skipping row: {e}"),
+        Err(e) => error!("Couldn't shred {name}: {e}"),
+    }
+}
+
 fn init_tracing() {
     let filter = EnvFilter::try_from_default_env()
         .or_else(|_| EnvFilter::try_new("shreddit"))
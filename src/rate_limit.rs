@@ -0,0 +1,138 @@
+use reqwest::header::HeaderMap;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::{Instant, sleep_until};
+use tracing::debug;
+
+// Reddit's rate limit as of 7/1/2023 is 100 queries per minute per OAuth client id, averaged over a
+// 10 minute window. Every OAuth response tells us how much of that budget is left:
+// - `X-Ratelimit-Used`: requests used in this window
+// - `X-Ratelimit-Remaining`: requests left in this window
+// - `X-Ratelimit-Reset`: seconds until the window resets
+//
+// Until Reddit has told us anything, pace requests at the documented rate.
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(600);
+
+/// Spreads requests evenly across Reddit's rate limit window, and blocks until the window resets
+/// once the budget is spent. Clones share the same budget.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter(Arc<Mutex<State>>);
+
+#[derive(Debug, Default)]
+struct State {
+    remaining: Option<f64>,
+    reset_at: Option<Instant>,
+    next_slot: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// Waits until it's this request's turn.
+    pub async fn acquire(&self) {
+        let slot = self.0.lock().unwrap().reserve(Instant::now());
+        let now = Instant::now();
+
+        if slot > now {
+            debug!("Sleeping for {:?} to prevent rate limiting.", slot - now);
+            sleep_until(slot).await;
+        }
+    }
+
+    /// Updates the remaining budget from a response's `X-Ratelimit-*` headers.
+    pub fn update(&self, headers: &HeaderMap) {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+        let remaining = header("x-ratelimit-remaining").and_then(|v| v.parse::<f64>().ok());
+        let reset = header("x-ratelimit-reset").and_then(|v| v.parse::<u64>().ok());
+        let used = header("x-ratelimit-used").and_then(|v| v.parse::<u64>().ok());
+
+        if remaining.is_none() && reset.is_none() {
+            return;
+        }
+
+        debug!("Rate limit: used {used:?}, remaining {remaining:?}, reset in {reset:?}s");
+
+        let mut state = self.0.lock().unwrap();
+        state.remaining = remaining.or(state.remaining);
+        if let Some(reset) = reset {
+            state.reset_at = Some(Instant::now() + Duration::from_secs(reset));
+        }
+    }
+
+    /// Marks the budget as spent, e.g. after a `429 Too Many Requests`.
+    pub fn exhaust(&self) {
+        self.0.lock().unwrap().remaining = Some(0.0);
+    }
+}
+
+impl State {
+    /// Reserves the next time a request may be sent.
+    fn reserve(&mut self, now: Instant) -> Instant {
+        let earliest = self.next_slot.map_or(now, |slot| slot.max(now));
+
+        let (slot, interval) = match (self.remaining, self.reset_at) {
+            // The window has reset since we last heard from Reddit.
+            (_, Some(reset_at)) if reset_at <= earliest => {
+                self.remaining = None;
+                self.reset_at = None;
+                (earliest, DEFAULT_INTERVAL)
+            }
+            (Some(remaining), Some(reset_at)) if remaining < 1.0 => (reset_at, DEFAULT_INTERVAL),
+            (Some(remaining), Some(reset_at)) => {
+                (earliest, (reset_at - earliest).div_f64(remaining))
+            }
+            (Some(remaining), None) if remaining < 1.0 => {
+                (earliest.max(now + DEFAULT_INTERVAL), DEFAULT_INTERVAL)
+            }
+            _ => (earliest, DEFAULT_INTERVAL),
+        };
+
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = (*remaining - 1.0).max(0.0);
+        }
+        self.next_slot = Some(slot + interval);
+
+        slot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paces_requests_across_window() {
+        let now = Instant::now();
+        let mut state = State {
+            remaining: Some(10.0),
+            reset_at: Some(now + Duration::from_secs(10)),
+            next_slot: None,
+        };
+
+        assert_eq!(state.reserve(now), now);
+        assert_eq!(state.reserve(now), now + Duration::from_secs(1));
+    }
+
+    #[test]
+    fn waits_for_reset_when_exhausted() {
+        let now = Instant::now();
+        let reset_at = now + Duration::from_secs(30);
+        let mut state = State {
+            remaining: Some(0.0),
+            reset_at: Some(reset_at),
+            next_slot: None,
+        };
+
+        assert_eq!(state.reserve(now), reset_at);
+    }
+
+    #[test]
+    fn defaults_without_headers() {
+        let now = Instant::now();
+        let mut state = State::default();
+
+        assert_eq!(state.reserve(now), now);
+        assert_eq!(state.reserve(now), now + DEFAULT_INTERVAL);
+    }
+}
@@ -0,0 +1,361 @@
+//! The text comments and self posts are overwritten with before they're deleted.
+
+use crate::things::LOREM_IPSUM;
+use clap::ValueEnum;
+use jiff::{Zoned, tz::TimeZone};
+use rand::{
+    RngExt, SeedableRng,
+    distr::Alphanumeric,
+    rngs::StdRng,
+    seq::{IndexedRandom, IteratorRandom},
+};
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// How to come up with the text things are overwritten with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReplacementStrategy {
+    /// Always the replacement comment.
+    #[default]
+    Fixed,
+    /// Random words of lorem ipsum.
+    RandomWords,
+    /// Random letters and digits.
+    RandomChars,
+    /// Random words, cut to the length of the original text.
+    MatchLength,
+}
+
+/// In which order edits use the phrases of a replacement file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum PhraseOrder {
+    /// A random phrase for every edit.
+    #[default]
+    Random,
+    /// One phrase after the other, starting over after the last.
+    RoundRobin,
+}
+
+/// Phrases to overwrite things with instead of the replacement comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phrases(Vec<String>);
+
+impl Phrases {
+    /// Reads a `.json` file as an array of strings, and any other file as a phrase per line.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("couldn't read {}: {e}", path.display()))?;
+
+        let phrases: Vec<String> = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&contents)
+                .map_err(|e| format!("{} isn't a JSON array of strings: {e}", path.display()))?
+        } else {
+            contents.lines().map(str::to_string).collect()
+        };
+
+        Self::new(phrases).ok_or_else(|| format!("{} has no phrases", path.display()))
+    }
+
+    /// Blank phrases are left out. Returns `None` if no phrases are left.
+    pub fn new(phrases: impl IntoIterator<Item = impl Into<String>>) -> Option<Self> {
+        let phrases: Vec<String> = phrases
+            .into_iter()
+            .map(Into::into)
+            .filter(|phrase| !phrase.trim().is_empty())
+            .collect();
+
+        (!phrases.is_empty()).then_some(Self(phrases))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+/// Generates replacement text, fresh for every edit, so overwritten things can't be told apart by
+/// their identical text.
+#[derive(Debug)]
+pub struct Replacer {
+    rng: Mutex<StdRng>,
+    /// The phrase to use next in [`PhraseOrder::RoundRobin`].
+    next_phrase: AtomicUsize,
+}
+
+impl Replacer {
+    /// Seeding the replacer makes the text it generates reproducible, e.g. for tests.
+    pub fn new(seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => rand::make_rng(),
+        };
+
+        Self {
+            rng: Mutex::new(rng),
+            next_phrase: AtomicUsize::new(0),
+        }
+    }
+
+    /// Picks the phrase for the next edit.
+    pub fn phrase<'a>(&self, phrases: &'a Phrases, order: PhraseOrder) -> &'a str {
+        let phrase = match order {
+            PhraseOrder::Random => phrases.0.choose(&mut *self.rng.lock().unwrap()),
+            PhraseOrder::RoundRobin => {
+                let next = self.next_phrase.fetch_add(1, Ordering::Relaxed);
+                phrases.0.get(next % phrases.0.len())
+            }
+        };
+
+        phrase.map_or("", String::as_str)
+    }
+
+    /// The text to overwrite `original` with. `fixed` is used as is by [`ReplacementStrategy::Fixed`].
+    pub fn text(&self, strategy: ReplacementStrategy, fixed: &str, original: &str) -> String {
+        let mut rng = self.rng.lock().unwrap();
+
+        match strategy {
+            ReplacementStrategy::Fixed => fixed.to_string(),
+            ReplacementStrategy::RandomWords => {
+                let count = rng.random_range(10..=40);
+                random_words(&mut rng, count)
+            }
+            ReplacementStrategy::RandomChars => {
+                let len = rng.random_range(50..=200);
+                (0..len).map(|_| rng.sample(Alphanumeric) as char).collect()
+            }
+            ReplacementStrategy::MatchLength => {
+                let len = original.chars().count();
+                if len == 0 {
+                    let count = rng.random_range(10..=40);
+                    return random_words(&mut rng, count);
+                }
+
+                let mut text = String::new();
+                while text.chars().count() < len {
+                    if !text.is_empty() {
+                        text.push(' ');
+                    }
+                    text.push_str(&random_word(&mut rng));
+                }
+
+                text.chars().take(len).collect()
+            }
+        }
+    }
+}
+
+/// What a replacement comment can refer to, e.g. `Removed from r/{subreddit} on {now}`.
+#[derive(Debug)]
+pub struct Placeholders<'a> {
+    pub id: &'a str,
+    pub subreddit: &'a str,
+    pub created: Zoned,
+    pub permalink: &'a str,
+}
+
+impl Placeholders<'_> {
+    /// Fills in `{id}`, `{subreddit}`, `{created}`, `{permalink}` and `{now}`. Dates are in UTC.
+    pub fn render(&self, template: &str) -> String {
+        template
+            .replace("{id}", self.id)
+            .replace("{subreddit}", self.subreddit)
+            .replace("{created}", &format_date(&self.created))
+            .replace("{permalink}", &permalink_url(self.permalink))
+            .replace("{now}", &format_date(&Zoned::now()))
+    }
+
+    /// Whether `text` is `template` rendered at any time, i.e. with anything in place of `{now}`.
+    pub fn matches(&self, template: &str, text: &str) -> bool {
+        let mut parts = template.split("{now}").map(|part| self.render(part));
+
+        let first = parts.next().unwrap_or_default();
+        let Some(mut rest) = text.strip_prefix(first.as_str()) else {
+            return false;
+        };
+
+        let parts: Vec<String> = parts.collect();
+        let Some((last, middle)) = parts.split_last() else {
+            return rest.is_empty();
+        };
+
+        for part in middle {
+            let Some(i) = rest.find(part.as_str()) else {
+                return false;
+            };
+            rest = &rest[i + part.len()..];
+        }
+
+        rest.ends_with(last.as_str())
+    }
+}
+
+fn format_date(date: &Zoned) -> String {
+    date.with_time_zone(TimeZone::UTC)
+        .strftime("%Y-%m-%d %H:%M:%S UTC")
+        .to_string()
+}
+
+/// Reddit's API has permalinks like `/r/rust/comments/...`, GDPR exports have full URLs.
+fn permalink_url(permalink: &str) -> String {
+    if permalink.starts_with('/') {
+        format!("https://www.reddit.com{permalink}")
+    } else {
+        permalink.to_string()
+    }
+}
+
+fn random_words(rng: &mut StdRng, count: usize) -> String {
+    (0..count)
+        .map(|_| random_word(rng))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn random_word(rng: &mut StdRng) -> String {
+    let word = LOREM_IPSUM
+        .split_whitespace()
+        .choose(rng)
+        .unwrap_or_default();
+
+    word.trim_matches(|c| c == ',' || c == '.').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_uses_the_replacement_comment() {
+        let replacer = Replacer::new(None);
+
+        assert_eq!(
+            replacer.text(ReplacementStrategy::Fixed, "[removed]", "original"),
+            "[removed]"
+        );
+    }
+
+    #[test]
+    fn random_text_is_fresh_for_every_edit() {
+        let replacer = Replacer::new(None);
+
+        for strategy in [
+            ReplacementStrategy::RandomWords,
+            ReplacementStrategy::RandomChars,
+            ReplacementStrategy::MatchLength,
+        ] {
+            let first = replacer.text(strategy, "[removed]", "some original text to overwrite");
+            let second = replacer.text(strategy, "[removed]", "some original text to overwrite");
+
+            assert_ne!(first, second, "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn match_length_matches_the_original_length() {
+        let replacer = Replacer::new(None);
+        let original = "Ünïcödé counts as characters, not bytes";
+
+        let text = replacer.text(ReplacementStrategy::MatchLength, "[removed]", original);
+
+        assert_eq!(text.chars().count(), original.chars().count());
+    }
+
+    #[test]
+    fn phrases_are_read_from_text_and_json_files() {
+        let dir = std::env::temp_dir().join(format!("shreddit-phrases-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let text = dir.join("phrases.txt");
+        fs::write(&text, "first phrase\n\nsecond phrase\n").unwrap();
+        let json = dir.join("phrases.json");
+        fs::write(&json, r#"["first phrase", "second\nphrase"]"#).unwrap();
+        let bracketed = dir.join("bracketed.txt");
+        fs::write(&bracketed, "[removed]\n[deleted]\n").unwrap();
+        let empty = dir.join("empty.txt");
+        fs::write(&empty, "\n").unwrap();
+
+        assert_eq!(
+            Phrases::load(&text).unwrap(),
+            Phrases(vec!["first phrase".into(), "second phrase".into()])
+        );
+        assert_eq!(
+            Phrases::load(&json).unwrap(),
+            Phrases(vec!["first phrase".into(), "second\nphrase".into()])
+        );
+        assert_eq!(
+            Phrases::load(&bracketed).unwrap(),
+            Phrases(vec!["[removed]".into(), "[deleted]".into()])
+        );
+        assert!(Phrases::load(&empty).is_err());
+    }
+
+    #[test]
+    fn round_robin_cycles_through_phrases() {
+        let replacer = Replacer::new(None);
+        let phrases = Phrases::new(["a", "b"]).unwrap();
+
+        let picked: Vec<&str> = (0..3)
+            .map(|_| replacer.phrase(&phrases, PhraseOrder::RoundRobin))
+            .collect();
+
+        assert_eq!(picked, ["a", "b", "a"]);
+    }
+
+    #[test]
+    fn placeholders_are_rendered() {
+        let placeholders = Placeholders {
+            id: "abc",
+            subreddit: "rust",
+            created: "2023-05-14 17:23:51+00:00[UTC]".parse().unwrap(),
+            permalink: "/r/rust/comments/xyz/post/abc/",
+        };
+
+        assert_eq!(
+            placeholders.render("{id} in r/{subreddit} from {created}: {permalink}"),
+            "abc in r/rust from 2023-05-14 17:23:51 UTC: https://www.reddit.com/r/rust/comments/xyz/post/abc/"
+        );
+        assert!(!placeholders.render("Removed on {now}").contains('{'));
+    }
+
+    #[test]
+    fn now_matches_any_time() {
+        let placeholders = Placeholders {
+            id: "abc",
+            subreddit: "rust",
+            created: "2023-05-14 17:23:51+00:00[UTC]".parse().unwrap(),
+            permalink: "/r/rust/comments/xyz/post/abc/",
+        };
+        let template = "Removed from r/{subreddit} on {now}, see our policy";
+
+        assert!(placeholders.matches(
+            template,
+            "Removed from r/rust on 2020-01-01 00:00:00 UTC, see our policy"
+        ));
+        assert!(placeholders.matches(template, &placeholders.render(template)));
+        assert!(!placeholders.matches(
+            template,
+            "Removed from r/golang on 2020-01-01 00:00:00 UTC, see our policy"
+        ));
+        assert!(!placeholders.matches(template, "Removed from r/rust on"));
+        assert!(placeholders.matches("[removed]", "[removed]"));
+        assert!(!placeholders.matches("[removed]", "[removed] and more"));
+    }
+
+    #[test]
+    fn seeded_text_is_reproducible() {
+        let first = Replacer::new(Some(7));
+        let second = Replacer::new(Some(7));
+
+        for _ in 0..3 {
+            assert_eq!(
+                first.text(ReplacementStrategy::RandomWords, "", ""),
+                second.text(ReplacementStrategy::RandomWords, "", "")
+            );
+        }
+    }
+}
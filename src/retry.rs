@@ -0,0 +1,55 @@
+use crate::cli::Config;
+use std::time::Duration;
+
+/// How often and how patiently to retry requests that failed for transient reasons.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl From<&Config> for RetryPolicy {
+    fn from(config: &Config) -> Self {
+        Self {
+            max_attempts: config.max_attempts.max(1),
+            base_delay: config.retry_delay,
+            max_delay: config.max_retry_delay,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait after the given (1-based) failed attempt. The delay doubles with every
+    /// attempt up to `max_delay`, and is jittered so retries don't line up.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+
+        exponential / 2 + exponential.mul_f64(rand::random_range(0.0..0.5))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_and_caps() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+        };
+
+        for (attempt, max) in [(1, 1), (2, 2), (3, 4), (4, 8), (5, 10), (30, 10)] {
+            let delay = policy.backoff(attempt);
+            let max = Duration::from_secs(max);
+
+            assert!(delay >= max / 2, "attempt {attempt}: {delay:?}");
+            assert!(delay <= max, "attempt {attempt}: {delay:?}");
+        }
+    }
+}
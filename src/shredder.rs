@@ -0,0 +1,348 @@
+use crate::{
+    cli::Config,
+    client::RedditClient,
+    edited::{self, EditedLog},
+    error::ShredditError,
+    replacement::{PhraseOrder, Phrases, ReplacementStrategy, Replacer},
+    sources,
+    things::{Outcome, ShredditSet, ThingType},
+};
+use async_stream::stream;
+use futures_core::Stream;
+use futures_util::StreamExt;
+use jiff::{Timestamp, Zoned};
+use std::{path::PathBuf, time::Duration};
+use tracing::{debug, error, info};
+
+/// Shreds the things a [`Config`] selects, reporting an [`Event`] for each of them.
+#[derive(Debug)]
+pub struct Shredder {
+    config: Config,
+    client: RedditClient,
+    /// Comes up with the text things are overwritten with, keeping its state across the run.
+    replacer: Replacer,
+}
+
+/// What happened to a single thing during a run.
+#[derive(Debug)]
+pub struct Event {
+    pub thing_type: ThingType,
+    /// The thing's name on Reddit, see [`Shred::name`](crate::things::Shred::name). Rows of a
+    /// GDPR export that couldn't be read are named by their file and line.
+    pub name: String,
+    pub outcome: Result<Outcome, ShredditError>,
+}
+
+impl Shredder {
+    pub fn new(config: Config) -> Self {
+        let client = RedditClient::new(&config);
+        let replacer = Replacer::new(config.replacement_seed);
+
+        Self {
+            config,
+            client,
+            replacer,
+        }
+    }
+
+    pub fn builder(
+        username: impl Into<String>,
+        password: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> ShredderBuilder {
+        ShredderBuilder {
+            config: Config::new(username, password, client_id, client_secret),
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Fetches an access token, so bad credentials can be reported before anything is listed.
+    pub async fn authenticate(&self) -> Result<(), ShredditError> {
+        self.client.access_token().await.map(|_| ())
+    }
+
+    /// Shreds every configured thing type in turn. Nothing happens until the stream is polled,
+    /// except that in two-phase mode (`delete_edited_after`) the edited log is read up front.
+    ///
+    /// Rows of a GDPR export that can't be read are reported as [`ShredditError::MalformedRow`]s.
+    /// With `gdpr_strict`, the run stops after the first of them.
+    ///
+    /// In two-phase mode, things overwritten by earlier runs are deleted first once they're due,
+    /// and things this run overwrites are recorded instead of deleted.
+    pub fn run(&self) -> Result<impl Stream<Item = Event> + '_, ShredditError> {
+        let edited = match self.config.delete_edited_after {
+            Some(_) => Some(EditedLog::load(&self.config.edited_file).map_err(|error| {
+                ShredditError::EditedLog {
+                    path: self.config.edited_file.clone(),
+                    error,
+                }
+            })?),
+            None => None,
+        };
+
+        Ok(stream! {
+            let source = sources::from_config(&self.config);
+            let mut edited = edited;
+
+            // In two-phase mode, things overwritten by earlier runs are deleted once they're due.
+            if let Some(grace) = self.config.delete_edited_after
+                && let Some(log) = &mut edited
+            {
+                let mut deleted = false;
+                for fullname in log.due(grace, Timestamp::now()) {
+                    let outcome = if self.config.dry_run {
+                        Ok(Outcome::DryRun)
+                    } else {
+                        info!("Deleting {fullname}, overwritten at least {grace:?} ago...");
+                        self.client.del(&fullname).await.map(|()| Outcome::Deleted)
+                    };
+
+                    if let Ok(Outcome::Deleted) = outcome {
+                        log.remove(&fullname);
+                        deleted = true;
+                    }
+
+                    yield Event {
+                        thing_type: edited::thing_type(&fullname),
+                        name: fullname,
+                        outcome,
+                    };
+                }
+
+                if deleted && let Err(e) = log.save() {
+                    error!("Couldn't update {}: {e}", log.path().display());
+                }
+            }
+
+            for thing_type in &self.config.thing_types {
+                info!("Shredding {thing_type:?}...");
+
+                let mut things = source.list(thing_type, &self.client, &self.config);
+
+                while let Some(thing) = things.next().await {
+                    let thing = match thing {
+                        Ok(thing) => thing,
+                        Err(row) => {
+                            yield Event {
+                                thing_type: thing_type.clone(),
+                                name: format!("{}:{}", row.file, row.line),
+                                outcome: Err(ShredditError::MalformedRow(row)),
+                            };
+
+                            if self.config.gdpr_strict {
+                                return;
+                            }
+
+                            continue;
+                        }
+                    };
+
+                    let name = thing.name();
+
+                    if let Some(log) = &edited
+                        && log.contains(&name)
+                    {
+                        debug!("Skipping because it was already overwritten and waits to be deleted");
+
+                        yield Event {
+                            thing_type: thing_type.clone(),
+                            name,
+                            outcome: Ok(Outcome::Skipped),
+                        };
+                        continue;
+                    }
+
+                    let outcome = thing
+                        .shred(&self.client, &self.config, &self.replacer)
+                        .await;
+
+                    if let Some(log) = &mut edited
+                        && let Ok(Outcome::Edited) = outcome
+                        && let Err(e) = log.record(&name)
+                    {
+                        error!("Couldn't record {name} in {}: {e}", log.path().display());
+                    }
+
+                    yield Event {
+                        thing_type: thing_type.clone(),
+                        name,
+                        outcome,
+                    };
+                }
+
+                info!("Completed shredding {thing_type:?}");
+            }
+        })
+    }
+}
+
+/// Builds a [`Shredder`], starting from the same defaults as the CLI.
+#[derive(Debug)]
+pub struct ShredderBuilder {
+    config: Config,
+}
+
+impl ShredderBuilder {
+    pub fn thing_types(mut self, thing_types: impl IntoIterator<Item = ThingType>) -> Self {
+        self.config.thing_types = thing_types.into_iter().collect();
+        self
+    }
+
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.config.dry_run = dry_run;
+        self
+    }
+
+    pub fn edit_only(mut self, edit_only: bool) -> Self {
+        self.config.edit_only = edit_only;
+        self
+    }
+
+    pub fn replacement_comment(mut self, replacement_comment: impl Into<String>) -> Self {
+        self.config.replacement_comment = replacement_comment.into();
+        self
+    }
+
+    pub fn replacement_strategy(mut self, replacement_strategy: ReplacementStrategy) -> Self {
+        self.config.replacement_strategy = replacement_strategy;
+        self
+    }
+
+    pub fn replacement_seed(mut self, replacement_seed: u64) -> Self {
+        self.config.replacement_seed = Some(replacement_seed);
+        self
+    }
+
+    /// Phrases to overwrite things with instead of the replacement comment. Blank ones are left out.
+    pub fn replacement_phrases(
+        mut self,
+        phrases: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.config.replacement_file = Phrases::new(phrases);
+        self
+    }
+
+    pub fn replacement_order(mut self, replacement_order: PhraseOrder) -> Self {
+        self.config.replacement_order = replacement_order;
+        self
+    }
+
+    /// Overwrite things this many times before deleting them, with random text on every pass but
+    /// the last.
+    pub fn overwrite_passes(mut self, overwrite_passes: u32) -> Self {
+        self.config.overwrite_passes = overwrite_passes.max(1);
+        self
+    }
+
+    pub fn overwrite_delay(mut self, overwrite_delay: Duration) -> Self {
+        self.config.overwrite_delay = Some(overwrite_delay);
+        self
+    }
+
+    pub fn before(mut self, before: Zoned) -> Self {
+        self.config.before = Some(before);
+        self
+    }
+
+    pub fn after(mut self, after: Zoned) -> Self {
+        self.config.after = Some(after);
+        self
+    }
+
+    pub fn max_score(mut self, max_score: i64) -> Self {
+        self.config.max_score = Some(max_score);
+        self
+    }
+
+    pub fn keep_gilded(mut self, keep_gilded: bool) -> Self {
+        self.config.keep_gilded = keep_gilded;
+        self
+    }
+
+    pub fn keep_distinguished(mut self, keep_distinguished: bool) -> Self {
+        self.config.keep_distinguished = keep_distinguished;
+        self
+    }
+
+    pub fn skip_subreddits(
+        mut self,
+        subreddits: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.config.skip_subreddits = Some(ShredditSet::from_iter(subreddits));
+        self
+    }
+
+    pub fn only_subreddits(
+        mut self,
+        subreddits: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.config.only_subreddits = Some(ShredditSet::from_iter(subreddits));
+        self
+    }
+
+    pub fn skip_comment_ids(mut self, ids: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.skip_comment_ids = Some(ShredditSet::from_iter(ids));
+        self
+    }
+
+    pub fn skip_post_ids(mut self, ids: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.skip_post_ids = Some(ShredditSet::from_iter(ids));
+        self
+    }
+
+    pub fn skip_message_users(
+        mut self,
+        users: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.config.skip_message_users = Some(ShredditSet::from_iter(users));
+        self
+    }
+
+    pub fn skip_friends(mut self, friends: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.skip_friends = Some(ShredditSet::from_iter(friends));
+        self
+    }
+
+    /// Discover things in a GDPR export, zipped or not, instead of through Reddit's API.
+    pub fn gdpr_export_dir(mut self, export_dir: impl Into<PathBuf>) -> Self {
+        self.config.gdpr_export_dir = Some(export_dir.into());
+        self
+    }
+
+    /// Two-phase mode: only overwrite things, and delete them in a later run once they've been
+    /// overwritten for `grace`. The things are recorded in `edited_file`.
+    pub fn delete_edited_after(mut self, grace: Duration, edited_file: impl Into<PathBuf>) -> Self {
+        self.config.delete_edited_after = Some(grace);
+        self.config.edited_file = edited_file.into();
+        self
+    }
+
+    /// Stop at the first row of the GDPR export that can't be read, instead of skipping it.
+    pub fn gdpr_strict(mut self, gdpr_strict: bool) -> Self {
+        self.config.gdpr_strict = gdpr_strict;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.config.user_agent = user_agent.into();
+        self
+    }
+
+    pub fn api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.config.api_url = api_url.into();
+        self
+    }
+
+    pub fn auth_url(mut self, auth_url: impl Into<String>) -> Self {
+        self.config.auth_url = auth_url.into();
+        self
+    }
+
+    pub fn build(self) -> Shredder {
+        Shredder::new(self.config)
+    }
+}
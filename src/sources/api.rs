@@ -1,3 +1,33 @@
+use super::{Source, Things, boxed};
+use crate::{
+    cli::Config,
+    client::RedditClient,
+    things::{
+        ThingType, comment, friend, hidden_post, message, post, saved_comment, saved_post,
+        subscription, vote,
+    },
+};
+use futures_util::StreamExt;
+
 pub trait Api {
     const TYPE_ID: &'static str;
 }
+
+/// Discovers things through Reddit's API. Note that Reddit's listings only go back about 1000 items.
+pub struct ApiSource;
+
+impl Source for ApiSource {
+    fn list(&self, thing_type: &ThingType, client: &RedditClient, config: &Config) -> Things {
+        match thing_type {
+            ThingType::Posts => boxed(post::list(client, config).map(Ok)),
+            ThingType::Comments => boxed(comment::list(client, config).map(Ok)),
+            ThingType::Friends => boxed(friend::list(client, config).map(Ok)),
+            ThingType::SavedPosts => boxed(saved_post::list(client, config).map(Ok)),
+            ThingType::SavedComments => boxed(saved_comment::list(client, config).map(Ok)),
+            ThingType::Votes => boxed(vote::list(client, config).map(Ok)),
+            ThingType::Messages => boxed(message::list(client, config).map(Ok)),
+            ThingType::Subscriptions => boxed(subscription::list(client, config).map(Ok)),
+            ThingType::HiddenPosts => boxed(hidden_post::list(client, config).map(Ok)),
+        }
+    }
+}
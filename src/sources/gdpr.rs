@@ -1,18 +1,264 @@
+use super::{Source, Things, boxed};
+use crate::{
+    cli::Config,
+    client::RedditClient,
+    things::{
+        Comment, CommentVote, Friend, HiddenPost, Message, Post, PostVote, SavedComment, SavedPost,
+        Shred, Subscription, ThingType,
+    },
+};
+use async_stream::stream;
 use csv::Reader;
-use serde::de::DeserializeOwned;
-use std::path::Path;
+use futures_core::Stream;
+use futures_util::{StreamExt, stream};
+use serde::{Deserialize, Deserializer, de::DeserializeOwned};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+use tracing::{debug, error, warn};
+use zip::{ZipArchive, result::ZipResult};
 
 pub trait Gdpr {
     const FILENAME: &'static str;
+    /// The columns of [`Self::FILENAME`] that are needed to deserialize a row.
+    const COLUMNS: &'static [&'static str];
 }
 
-pub fn list<T>(export_dir: &Path) -> impl Iterator<Item = T>
+/// A row of a GDPR export that couldn't be read, e.g. because Reddit changed the CSV's columns.
+#[derive(Debug, Clone)]
+pub struct MalformedRow {
+    pub file: &'static str,
+    /// The 1-based line of the row, where line 1 is the header.
+    pub line: u64,
+    pub message: String,
+    /// Whether the whole file was skipped, because its header couldn't be read or lacks columns.
+    pub skipped_file: bool,
+}
+
+impl MalformedRow {
+    fn new(file: &'static str, e: csv::Error) -> Self {
+        Self {
+            file,
+            line: e.position().map_or(0, |p| p.line()),
+            message: e.to_string(),
+            skipped_file: false,
+        }
+    }
+}
+
+/// Streams the things in `T::FILENAME` of the export at `export`, which is either the `.zip`
+/// Reddit sent or the directory it was extracted to. Zipped CSVs are read straight from the archive.
+///
+/// Rows that can't be deserialized are yielded as [`MalformedRow`]s. If the header is missing any
+/// of `T::COLUMNS`, that's reported once and no rows are read.
+pub fn list<T>(export: &Path) -> impl Stream<Item = Result<T, MalformedRow>> + Send + use<T>
+where
+    T: Gdpr + DeserializeOwned + Send,
+{
+    let export = export.to_path_buf();
+
+    stream! {
+        let mut export = match Export::open(&export) {
+            Ok(export) => export,
+            Err(e) => {
+                error!("Couldn't open GDPR export {}: {e}", export.display());
+                return;
+            }
+        };
+
+        let file = match export.file(T::FILENAME) {
+            Ok(Some(file)) => file,
+            Ok(None) => {
+                warn!("GDPR export has no {}, so there's nothing to shred from it", T::FILENAME);
+                return;
+            }
+            Err(e) => {
+                error!("Couldn't read {} from GDPR export: {e}", T::FILENAME);
+                return;
+            }
+        };
+
+        let mut reader = Reader::from_reader(file);
+
+        let missing: Vec<&str> = match reader.headers() {
+            Ok(headers) => T::COLUMNS
+                .iter()
+                .filter(|&&column| !headers.iter().any(|h| h == column))
+                .copied()
+                .collect(),
+            Err(e) => {
+                yield Err(MalformedRow {
+                    skipped_file: true,
+                    ..MalformedRow::new(T::FILENAME, e)
+                });
+                return;
+            }
+        };
+
+        if !missing.is_empty() {
+            yield Err(MalformedRow {
+                file: T::FILENAME,
+                line: 1,
+                message: format!("missing columns: {}", missing.join(", ")),
+                skipped_file: true,
+            });
+            return;
+        }
+
+        for thing in reader.into_deserialize() {
+            yield thing.map_err(|e| MalformedRow::new(T::FILENAME, e));
+        }
+    }
+}
+
+/// Deserializes a count column like `gildings`, which is empty rather than `0` in some exports.
+/// Flattened fields are buffered before their type is known, so the count may arrive as a
+/// number or as a string.
+pub fn count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Count {
+        Number(u32),
+        Text(String),
+    }
+
+    match Count::deserialize(deserializer)? {
+        Count::Number(n) => Ok(n),
+        Count::Text(s) if s.trim().is_empty() => Ok(0),
+        Count::Text(s) => s.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+enum Export {
+    Dir(PathBuf),
+    Zip(ZipArchive<File>),
+}
+
+impl Export {
+    fn open(path: &Path) -> ZipResult<Self> {
+        if path.is_dir() {
+            return Ok(Self::Dir(path.to_path_buf()));
+        }
+
+        Ok(Self::Zip(ZipArchive::new(File::open(path)?)?))
+    }
+
+    /// Opens a CSV in the export, or returns `None` if the export doesn't have it. Zipped CSVs are
+    /// looked up by name, since some archives put them in a top-level directory.
+    fn file(&mut self, filename: &str) -> ZipResult<Option<Box<dyn Read + Send + '_>>> {
+        match self {
+            Self::Dir(dir) => match File::open(dir.join(filename)) {
+                Ok(file) => Ok(Some(Box::new(file))),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            },
+            Self::Zip(archive) => {
+                let Some(index) = (0..archive.len()).find(|&i| {
+                    archive.name_for_index(i).is_some_and(|name| {
+                        name.is_ok_and(|name| {
+                            Path::new(name.as_ref())
+                                .file_name()
+                                .is_some_and(|name| name == filename)
+                        })
+                    })
+                }) else {
+                    return Ok(None);
+                };
+
+                Ok(Some(Box::new(archive.by_index(index)?)))
+            }
+        }
+    }
+}
+
+/// Discovers things in a GDPR export, zipped or not.
+pub struct GdprSource {
+    export_dir: PathBuf,
+}
+
+impl GdprSource {
+    pub fn new(export_dir: &Path) -> Self {
+        Self {
+            export_dir: export_dir.to_path_buf(),
+        }
+    }
+}
+
+impl Source for GdprSource {
+    fn list(&self, thing_type: &ThingType, client: &RedditClient, _config: &Config) -> Things {
+        let export_dir = &self.export_dir;
+
+        match thing_type {
+            ThingType::Comments => boxed(hydrated(list::<Comment>(export_dir), client)),
+            ThingType::Friends => boxed(list::<Friend>(export_dir)),
+            ThingType::Posts => boxed(hydrated(list::<Post>(export_dir), client)),
+            ThingType::SavedPosts => boxed(list::<SavedPost>(export_dir)),
+            ThingType::SavedComments => boxed(list::<SavedComment>(export_dir)),
+            ThingType::Votes => boxed(list::<PostVote>(export_dir))
+                .chain(boxed(list::<CommentVote>(export_dir)))
+                .boxed(),
+            ThingType::Messages => boxed(list::<Message>(export_dir)),
+            ThingType::Subscriptions => boxed(list::<Subscription>(export_dir)),
+            ThingType::HiddenPosts => boxed(list::<HiddenPost>(export_dir)),
+        }
+    }
+}
+
+/// How many things Reddit's `api/info` endpoint looks up at most per request.
+const INFO_BATCH_SIZE: usize = 100;
+
+/// Replaces things with their current versions from Reddit's API, which unlike GDPR exports know
+/// e.g. their score, whether they were distinguished and whether they were already deleted. Things
+/// are looked up in batches, and ones Reddit doesn't return are left as they are.
+fn hydrated<T>(
+    things: impl Stream<Item = Result<T, MalformedRow>> + Send + 'static,
+    client: &RedditClient,
+) -> impl Stream<Item = Result<T, MalformedRow>> + Send + 'static
+where
+    T: Shred + DeserializeOwned + 'static,
+{
+    let client = client.clone();
+
+    things
+        .chunks(INFO_BATCH_SIZE)
+        .then(move |batch| {
+            let client = client.clone();
+
+            async move { hydrate(batch, &client).await }
+        })
+        .flat_map(stream::iter)
+}
+
+async fn hydrate<T>(
+    batch: Vec<Result<T, MalformedRow>>,
+    client: &RedditClient,
+) -> Vec<Result<T, MalformedRow>>
 where
-    T: Gdpr + DeserializeOwned,
+    T: Shred + DeserializeOwned,
 {
-    let mut p = export_dir.to_path_buf();
-    p.push(T::FILENAME);
+    let fullnames: Vec<String> = batch.iter().flatten().map(|thing| thing.name()).collect();
+    if fullnames.is_empty() {
+        return batch;
+    }
+
+    debug!("Getting {} things from API...", fullnames.len());
+
+    let mut hydrated: HashMap<String, T> = match client.info::<T>(&fullnames).await {
+        Ok(things) => things
+            .into_iter()
+            .map(|thing| (thing.name(), thing))
+            .collect(),
+        Err(e) => {
+            warn!("Couldn't get {} things from API: {e}", fullnames.len());
+            return batch;
+        }
+    };
 
-    let things = Reader::from_path(p).unwrap();
-    things.into_deserialize().map(|f| f.unwrap())
+    batch
+        .into_iter()
+        .map(|thing| thing.map(|thing| hydrated.remove(&thing.name()).unwrap_or(thing)))
+        .collect()
 }
@@ -1,2 +1,37 @@
 pub mod api;
 pub mod gdpr;
+
+use crate::{
+    cli::Config,
+    client::RedditClient,
+    things::{Shred, ThingType},
+};
+use api::ApiSource;
+use futures_core::Stream;
+use futures_util::{StreamExt, stream::BoxStream};
+use gdpr::{GdprSource, MalformedRow};
+
+/// Things to shred, or rows of a GDPR export that couldn't be read.
+pub type Things = BoxStream<'static, Result<Box<dyn Shred>, MalformedRow>>;
+
+/// Somewhere to discover the things to shred.
+pub trait Source {
+    fn list(&self, thing_type: &ThingType, client: &RedditClient, config: &Config) -> Things;
+}
+
+/// Uses the GDPR export if one was given, and Reddit's API otherwise.
+pub fn from_config(config: &Config) -> Box<dyn Source> {
+    match &config.gdpr_export_dir {
+        Some(export_dir) => Box::new(GdprSource::new(export_dir)),
+        None => Box::new(ApiSource),
+    }
+}
+
+fn boxed<T>(things: impl Stream<Item = Result<T, MalformedRow>> + Send + 'static) -> Things
+where
+    T: Shred + 'static,
+{
+    things
+        .map(|t| t.map(|t| Box::new(t) as Box<dyn Shred>))
+        .boxed()
+}
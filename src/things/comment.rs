@@ -1,17 +1,20 @@
-use super::{Shred, ShredditError};
+use super::{Shred, overwrite};
 use crate::{
     cli::Config,
-    sources::{api::Api, gdpr::Gdpr},
+    client::RedditClient,
+    error::ShredditError,
+    replacement::{Placeholders, Replacer},
+    sources::{
+        api::Api,
+        gdpr::{self, Gdpr},
+    },
 };
-use async_stream::stream;
 use async_trait::async_trait;
 use futures_core::Stream;
 use jiff::{Timestamp, Zoned, tz::TimeZone};
-use reqwest::{Client, header::HeaderMap};
 use serde::Deserialize;
 use serde_json::Value;
-use std::collections::HashMap;
-use tracing::{debug, error, info, instrument};
+use tracing::{debug, error, info, instrument, warn};
 
 #[allow(unused)]
 #[derive(Debug, Deserialize)]
@@ -30,15 +33,29 @@ pub struct Comment {
 enum Source {
     Api {
         score: i64,
-        created_utc: f32,
+        created_utc: f64,
         can_gild: bool,
+        #[serde(default)]
+        gilded: u32,
+        #[serde(default)]
+        all_awardings: Vec<Value>,
+        /// Set to e.g. `moderator` or `admin` if it was distinguished.
+        #[serde(default)]
+        distinguished: Option<String>,
+        /// Archived things can't be edited anymore, only deleted.
+        #[serde(default)]
+        archived: bool,
+        /// `[deleted]` once it was deleted.
+        #[serde(default)]
+        author: Option<String>,
     },
 
     // GDPR columns
     // id,permalink,date,ip,subreddit,gildings,link,parent,body,media
     Gdpr {
         date: Zoned,
-        subreddit: String,
+        #[serde(default, deserialize_with = "gdpr::count")]
+        gildings: u32,
     },
 }
 
@@ -48,152 +65,20 @@ impl Api for Comment {
 
 impl Gdpr for Comment {
     const FILENAME: &'static str = "comments.csv";
+    const COLUMNS: &'static [&'static str] = &["id", "permalink", "date", "subreddit", "body"];
 }
 
 #[async_trait]
 impl Shred for Comment {
-    #[instrument(level = "info", skip(client, access_token))]
-    async fn delete(&self, client: &Client, access_token: &str, config: &Config) {
-        info!("Deleting...");
-
-        if self.should_skip(config) || config.should_prevent_deletion() {
-            return;
-        }
-
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            format!("Bearer {access_token}").parse().unwrap(),
-        );
-
-        headers.insert("User-Agent", config.user_agent.parse().unwrap());
-
-        let params = HashMap::from([("id", self.fullname())]);
-
-        let _res = client
-            .post("https://oauth.reddit.com/api/del")
-            .headers(headers)
-            .form(&params)
-            .header("User-Agent", config.user_agent.clone())
-            .send()
-            .await
-            .unwrap();
-
-        self.prevent_rate_limit().await;
-    }
-
-    #[instrument(level = "debug", skip(client, access_token))]
-    async fn edit(&self, client: &Client, access_token: &str, config: &Config) {
-        #[allow(unused)]
-        #[derive(Debug, Deserialize)]
-        #[serde(untagged)]
-        enum EditResponse {
-            Success { jquery: Vec<Value>, success: bool },
-            Unexpected(Value),
-        }
-
-        debug!("Editing...");
-
-        if self.should_skip(config) {
-            return;
-        }
-
-        if config.dry_run {
-            return;
-        }
-
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            format!("Bearer {access_token}").parse().unwrap(),
-        );
-
-        headers.insert("User-Agent", config.user_agent.parse().unwrap());
-
-        let params = HashMap::from([
-            ("thing_id", self.fullname()),
-            ("text", config.replacement_comment.to_string()),
-        ]);
-
-        let res: EditResponse = client
-            .post("https://oauth.reddit.com/api/editusertext?raw_json=1")
-            .headers(headers)
-            .form(&params)
-            .send()
-            .await
-            .unwrap()
-            .json()
-            .await
-            .unwrap();
-
-        match res {
-            EditResponse::Success { jquery, .. } => {
-                if jquery.iter().any(|array| {
-                    array.as_array().unwrap().iter().any(|item| match item {
-                        Value::Array(a) => a.contains(&Value::String(
-                            ".error.RATELIMIT.field-ratelimit".to_string(),
-                        )),
-                        _ => false,
-                    })
-                }) {
-                    error!("RATE LIMITED");
-                }
-            }
-            EditResponse::Unexpected(v) => match self.source {
-                Source::Api { can_gild, .. } => {
-                    if !can_gild {
-                        error!(
-                            "Couldn't edit - comment was probably removed by a moderator (`can_gild` == {})",
-                            can_gild
-                        );
-                    } else {
-                        error!("Couldn't edit: {v:#?}");
-                    }
-                }
-                Source::Gdpr { .. } => {
-                    let Ok(comment) = self.to_api(client, access_token, config).await else {
-                        return;
-                    };
-
-                    match comment.source {
-                        Source::Api { can_gild, .. } => {
-                            if !can_gild {
-                                error!(
-                                    "Couldn't edit - comment was probably removed by a moderator (`can_gild` == {})",
-                                    can_gild
-                                );
-                            } else {
-                                error!("Couldn't edit: {v:#?}");
-                            }
-                        }
-                        Source::Gdpr { .. } => {
-                            unreachable!()
-                        }
-                    }
-                }
-            },
-        };
-
-        self.prevent_rate_limit().await;
-    }
-}
-
-impl Comment {
-    /// The Reddit API uses floats for timestamps, which can't be deserialized to [`DateTime`]s. This converts the float to a datetime.
-    pub fn created(&self) -> Zoned {
-        match &self.source {
-            Source::Api { created_utc, .. } => Timestamp::from_second(*created_utc as i64)
-                .unwrap()
-                .to_zoned(TimeZone::UTC),
-            Source::Gdpr { date, .. } => date.clone(),
-        }
-    }
-
-    fn fullname(&self) -> String {
-        format!("{}_{}", Self::TYPE_ID, self.id)
+    fn name(&self) -> String {
+        self.fullname()
     }
 
     fn should_skip(&self, config: &Config) -> bool {
+        if self.is_deleted() {
+            debug!("Skipping because it was already deleted");
+            return true;
+        }
         if let Some(skip_comment_ids) = &config.skip_comment_ids
             && skip_comment_ids.contains(&self.id)
         {
@@ -219,7 +104,23 @@ impl Comment {
             return true;
         }
         match &self.source {
-            Source::Api { score, .. } => {
+            Source::Api {
+                score,
+                gilded,
+                all_awardings,
+                distinguished,
+                ..
+            } => {
+                if config.keep_distinguished && distinguished.is_some() {
+                    debug!("Skipping due to `keep_distinguished` filter");
+                    return true;
+                }
+
+                if config.keep_gilded && (*gilded > 0 || !all_awardings.is_empty()) {
+                    debug!("Skipping due to `keep_gilded` filter");
+                    return true;
+                }
+
                 if let Some(max_score) = config.max_score
                     && *score > max_score
                 {
@@ -234,9 +135,19 @@ impl Comment {
                     return true;
                 }
             }
-            Source::Gdpr { .. } => {
+            Source::Gdpr { gildings, .. } => {
+                if config.keep_distinguished {
+                    error!("Cannot tell whether this was distinguished without Reddit's API");
+                    return true;
+                }
+
+                if config.keep_gilded && *gildings > 0 {
+                    debug!("Skipping due to `keep_gilded` filter");
+                    return true;
+                }
+
                 if config.max_score.is_some() {
-                    error!("Cannot filter by max score when using GDPR data");
+                    error!("Cannot filter by max score without Reddit's API");
                     return true;
                 }
 
@@ -259,159 +170,110 @@ impl Comment {
         false
     }
 
-    async fn to_api(
-        &self,
-        client: &Client,
-        access_token: &str,
-        config: &Config,
-    ) -> Result<Self, ShredditError> {
-        debug!("Getting comment from API...");
+    #[instrument(level = "info", skip(client))]
+    async fn delete(&self, client: &RedditClient, _config: &Config) -> Result<(), ShredditError> {
+        info!("Deleting...");
 
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            format!("Bearer {access_token}").parse().unwrap(),
-        );
+        client.del(&self.fullname()).await
+    }
 
-        headers.insert("User-Agent", config.user_agent.parse().unwrap());
+    #[instrument(level = "debug", skip(client, replacer))]
+    async fn edit(
+        &self,
+        client: &RedditClient,
+        config: &Config,
+        replacer: &Replacer,
+    ) -> Result<bool, ShredditError> {
+        debug!("Editing...");
 
-        let uri = format!(
-            "https://oauth.reddit.com/api/info.json?id={}",
-            self.fullname()
-        );
+        if let Source::Api { archived: true, .. } = self.source {
+            debug!("Not editing archived comment");
+            return Ok(false);
+        }
 
-        let res: Response = client
-            .get(&uri)
-            .headers(headers)
-            .send()
-            .await
-            .unwrap()
-            .json()
-            .await
-            .unwrap();
+        if config.is_replacement(&self.body, &self.placeholders()) {
+            debug!("Not editing comment that was already overwritten");
+            return Ok(false);
+        }
 
-        match res {
-            Response::Success { data } => match data.children.into_iter().next() {
-                Some(c) => Ok(c.data),
-                None => {
-                    error!("Couldn't get comment from API: No data returned");
-                    Err(ShredditError::Unknown)
+        match overwrite(
+            client,
+            config,
+            replacer,
+            &self.fullname(),
+            &self.body,
+            &self.placeholders(),
+        )
+        .await
+        {
+            Err(ShredditError::Unexpected(v)) => {
+                let can_gild = match self.source {
+                    Source::Api { can_gild, .. } => can_gild,
+                    Source::Gdpr { .. } => match self.to_api(client).await?.source {
+                        Source::Api { can_gild, .. } => can_gild,
+                        Source::Gdpr { .. } => unreachable!(),
+                    },
+                };
+
+                if !can_gild {
+                    warn!(
+                        "Comment was probably removed by a moderator (`can_gild` == {})",
+                        can_gild
+                    );
                 }
-            },
-            Response::Error(e) => {
-                error!("Couldn't get comment from API: {e:#?}");
-                Err(ShredditError::Unknown)
+
+                Err(ShredditError::Unexpected(v))
             }
+            res => res.map(|()| true),
         }
     }
 }
 
-/// https://www.reddit.com/dev/api/#GET_user_{username}_submitted
-#[instrument(level = "info", skip_all)]
-pub async fn list(
-    client: &Client,
-    access_token: &str,
-    config: &Config,
-) -> impl Stream<Item = Comment> {
-    info!("Fetching comments...");
-
-    let username = config.username.to_owned();
-    let client = client.clone();
-    let user_agent = config.user_agent.clone();
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        format!("Bearer {access_token}").parse().unwrap(),
-    );
-    headers.insert("User-Agent", user_agent.parse().unwrap());
-
-    stream! {
-    let mut last_seen = None;
-
-        loop {
-    let query_params = if let Some(last_seen) = last_seen {
-        format!("?after={last_seen}&limit=100")
-    } else {
-        "?limit=100".to_string()
-    };
-
-    let uri = format!("https://oauth.reddit.com/user/{username}/comments.json{query_params}");
-
-            let res: Response = client
-        .get(&uri)
-        .headers(headers.clone())
-        .send()
-        .await
-        .unwrap()
-        .json()
-        .await
-        .unwrap();
-
-    match res {
-        Response::Success { data} => {
-
-    let results_len = data.children.len();
-
-    debug!("Page contained {results_len} results");
-
-    if results_len == 0 {
-                break;
-    } else {
-                last_seen = data.children.last().map(|t| t.data.fullname());
+impl Comment {
+    /// The Reddit API uses floats for timestamps, which can't be deserialized to [`DateTime`]s. This converts the float to a datetime.
+    pub fn created(&self) -> Zoned {
+        match &self.source {
+            Source::Api { created_utc, .. } => Timestamp::from_second(*created_utc as i64)
+                .unwrap()
+                .to_zoned(TimeZone::UTC),
+            Source::Gdpr { date, .. } => date.clone(),
+        }
     }
 
-    for comment in data.children {
-                yield comment.data;
+    /// Only known for comments from Reddit's API, whose author becomes `[deleted]`.
+    fn is_deleted(&self) -> bool {
+        matches!(&self.source, Source::Api { author: Some(author), .. } if author == "[deleted]")
     }
-        }
-        Response::Error(e) => {
-    error!("Failed to fetch comments: {e:#?}");
-    break
-        }
 
+    fn placeholders(&self) -> Placeholders<'_> {
+        Placeholders {
+            id: &self.id,
+            subreddit: &self.subreddit,
+            created: self.created(),
+            permalink: &self.permalink,
+        }
     }
 
-        }
+    fn fullname(&self) -> String {
+        format!("{}_{}", Self::TYPE_ID, self.id)
     }
-}
 
-// #[derive(Debug, Deserialize)]
-// #[serde(tag = "kind", content = "data")]
-// pub enum Thing {
-//     #[serde(rename = "t1")]
-//     Comment {
-//         id: String,
-//         body: String,
-//         permalink: String,
-//         created_utc: f32,
-//         score: i64,
-//     },
-//     #[serde(rename = "t3")]
-//     Post {
-//         id: String,
-//         selftext: String,
-//         permalink: String,
-//         title: String,
-//         created_utc: f32,
-//         score: i64,
-//     },
-// }
+    async fn to_api(&self, client: &RedditClient) -> Result<Self, ShredditError> {
+        debug!("Getting comment from API...");
 
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-enum Response {
-    Success { data: ResponseData },
-    Error(Value),
+        client
+            .info(&[self.fullname()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ShredditError::NotFound(self.fullname()))
+    }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ResponseData {
-    pub children: Vec<Child>,
-    // pub after: Option<String>,
-    // pub before: Option<String>,
-}
+/// https://www.reddit.com/dev/api/#GET_user_{username}_comments
+#[instrument(level = "info", skip_all)]
+pub fn list(client: &RedditClient, config: &Config) -> impl Stream<Item = Comment> + use<> {
+    info!("Fetching comments...");
 
-#[derive(Debug, Deserialize)]
-pub struct Child {
-    pub data: Comment,
+    client.listing(&format!("/user/{}/comments", config.username))
 }
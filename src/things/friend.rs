@@ -1,51 +1,66 @@
 use super::Shred;
-use crate::{cli::Config, sources::gdpr::Gdpr};
+use crate::{cli::Config, client::RedditClient, error::ShredditError, sources::gdpr::Gdpr};
+use async_stream::stream;
 use async_trait::async_trait;
-use reqwest::{Client, header::HeaderMap};
+use futures_core::Stream;
 use serde::Deserialize;
 use std::fmt::Debug;
-use tracing::{error, info, instrument};
+use tracing::{debug, error, info, instrument};
 
 #[derive(Debug, Deserialize)]
 pub struct Friend {
+    /// Reddit's API calls this `name`, GDPR exports call it `username`.
+    #[serde(alias = "name")]
     username: String,
 }
 
 #[async_trait]
 impl Shred for Friend {
-    #[instrument(level = "info", skip(client, access_token))]
-    async fn delete(&self, client: &Client, access_token: &str, config: &Config) {
-        info!("Deleting...");
+    fn name(&self) -> String {
+        self.username.clone()
+    }
 
-        if config.should_prevent_deletion() {
-            return;
+    fn should_skip(&self, config: &Config) -> bool {
+        if let Some(skip_friends) = &config.skip_friends
+            && skip_friends
+                .iter()
+                .any(|friend| friend.eq_ignore_ascii_case(&self.username))
+        {
+            debug!("Skipping due to `skip_friends` filter");
+            return true;
         }
 
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            format!("Bearer {access_token}").parse().unwrap(),
-        );
-
-        headers.insert("User-Agent", config.user_agent.parse().unwrap());
-
-        let uri = format!(
-            "https://oauth.reddit.com/api/v1/me/friends/{}",
-            self.username
-        );
-
-        let res = client.delete(&uri).headers(headers).send().await.unwrap();
+        false
+    }
 
-        if res.status().is_success() {
-            info!("Deleted!");
-        } else {
-            error!("Failed to delete");
-        }
+    #[instrument(level = "info", skip(client))]
+    async fn delete(&self, client: &RedditClient, _config: &Config) -> Result<(), ShredditError> {
+        info!("Deleting...");
 
-        self.prevent_rate_limit().await;
+        client.unfriend(&self.username).await
     }
 }
 
 impl Gdpr for Friend {
     const FILENAME: &'static str = "friends.csv";
+    const COLUMNS: &'static [&'static str] = &["username"];
+}
+
+/// https://www.reddit.com/dev/api/#GET_api_v1_me_friends
+#[instrument(level = "info", skip_all)]
+pub fn list(client: &RedditClient, _config: &Config) -> impl Stream<Item = Friend> + use<> {
+    info!("Fetching friends...");
+
+    let client = client.clone();
+
+    stream! {
+        match client.friends().await {
+            Ok(friends) => {
+                for friend in friends {
+                    yield friend;
+                }
+            }
+            Err(e) => error!("Failed to fetch friends: {e}"),
+        }
+    }
 }
@@ -0,0 +1,70 @@
+use async_trait::async_trait;
+use futures_core::Stream;
+use serde::Deserialize;
+use tracing::{info, instrument};
+
+use crate::{
+    cli::Config,
+    client::RedditClient,
+    error::ShredditError,
+    sources::{api::Api, gdpr::Gdpr},
+};
+
+use super::{Shred, saved_post::should_skip_post, subreddit_from_permalink};
+
+#[derive(Debug, Deserialize)]
+pub struct HiddenPost {
+    id: String,
+    permalink: String,
+    /// Only Reddit's API has this, GDPR exports only have the permalink.
+    subreddit: Option<String>,
+}
+
+impl HiddenPost {
+    fn fullname(&self) -> String {
+        format!("{}_{}", Self::TYPE_ID, self.id)
+    }
+
+    fn subreddit(&self) -> Option<&str> {
+        self.subreddit
+            .as_deref()
+            .or_else(|| subreddit_from_permalink(&self.permalink))
+    }
+}
+
+// GDPR columns
+// id,permalink
+impl Gdpr for HiddenPost {
+    const FILENAME: &'static str = "hidden_posts.csv";
+    const COLUMNS: &'static [&'static str] = &["id", "permalink"];
+}
+
+impl Api for HiddenPost {
+    const TYPE_ID: &'static str = "t3";
+}
+
+#[async_trait]
+impl Shred for HiddenPost {
+    fn name(&self) -> String {
+        self.fullname()
+    }
+
+    fn should_skip(&self, config: &Config) -> bool {
+        should_skip_post(config, &self.id, self.subreddit())
+    }
+
+    #[instrument(level = "info", skip(client))]
+    async fn delete(&self, client: &RedditClient, _config: &Config) -> Result<(), ShredditError> {
+        info!("Unhiding...");
+
+        client.unhide(&self.fullname()).await
+    }
+}
+
+/// https://www.reddit.com/dev/api/#GET_user_{username}_hidden
+#[instrument(level = "info", skip_all)]
+pub fn list(client: &RedditClient, config: &Config) -> impl Stream<Item = HiddenPost> + use<> {
+    info!("Fetching hidden posts...");
+
+    client.listing(&format!("/user/{}/hidden", config.username))
+}
@@ -0,0 +1,162 @@
+use super::Shred;
+use crate::{
+    cli::Config,
+    client::RedditClient,
+    error::ShredditError,
+    sources::{api::Api, gdpr::Gdpr},
+};
+use async_trait::async_trait;
+use futures_core::Stream;
+use futures_util::{StreamExt, future::ready};
+use jiff::{Timestamp, Zoned, tz::TimeZone};
+use serde::Deserialize;
+use tracing::{debug, info, instrument};
+
+#[derive(Debug, Deserialize)]
+pub struct Message {
+    id: String,
+    #[serde(flatten)]
+    source: Source,
+}
+
+#[allow(unused)]
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Source {
+    Api {
+        author: Option<String>,
+        dest: String,
+        /// Set for messages sent from or to a subreddit, e.g. by its moderators.
+        subreddit: Option<String>,
+        created_utc: f64,
+        /// The inbox also lists replies to your comments, which aren't messages.
+        was_comment: bool,
+    },
+
+    // GDPR columns
+    // id,permalink,thread_id,date,ip,from,to,subject,body
+    Gdpr {
+        date: Zoned,
+        from: String,
+        to: String,
+    },
+}
+
+impl Api for Message {
+    const TYPE_ID: &'static str = "t4";
+}
+
+impl Gdpr for Message {
+    const FILENAME: &'static str = "messages.csv";
+    const COLUMNS: &'static [&'static str] = &["id", "date", "from", "to"];
+}
+
+impl Message {
+    pub fn created(&self) -> Zoned {
+        match &self.source {
+            Source::Api { created_utc, .. } => Timestamp::from_second(*created_utc as i64)
+                .unwrap()
+                .to_zoned(TimeZone::UTC),
+            Source::Gdpr { date, .. } => date.clone(),
+        }
+    }
+
+    fn fullname(&self) -> String {
+        format!("{}_{}", Self::TYPE_ID, self.id)
+    }
+
+    /// The users the message was sent from and to.
+    fn users(&self) -> impl Iterator<Item = &str> {
+        let (from, to) = match &self.source {
+            Source::Api { author, dest, .. } => (author.as_deref(), dest.as_str()),
+            Source::Gdpr { from, to, .. } => (Some(from.as_str()), to.as_str()),
+        };
+
+        from.into_iter().chain([to])
+    }
+
+    fn subreddit(&self) -> Option<&str> {
+        match &self.source {
+            Source::Api { subreddit, .. } => subreddit.as_deref(),
+            Source::Gdpr { .. } => None,
+        }
+    }
+
+    fn was_comment(&self) -> bool {
+        matches!(
+            self.source,
+            Source::Api {
+                was_comment: true,
+                ..
+            }
+        )
+    }
+}
+
+#[async_trait]
+impl Shred for Message {
+    fn name(&self) -> String {
+        self.fullname()
+    }
+
+    fn should_skip(&self, config: &Config) -> bool {
+        if let Some(skip_message_users) = &config.skip_message_users
+            && self.users().any(|user| {
+                skip_message_users
+                    .iter()
+                    .any(|skipped| skipped.eq_ignore_ascii_case(user))
+            })
+        {
+            debug!("Skipping due to `skip_message_users` filter");
+            return true;
+        }
+        if let Some(skip_subreddits) = &config.skip_subreddits
+            && self
+                .subreddit()
+                .is_some_and(|s| skip_subreddits.contains(s))
+        {
+            debug!("Skipping due to `skip_subreddits` filter");
+            return true;
+        }
+        if let Some(only_subreddits) = &config.only_subreddits
+            && !self
+                .subreddit()
+                .is_some_and(|s| only_subreddits.contains(s))
+        {
+            debug!("Skipping due to `only_subreddits` filter");
+            return true;
+        }
+        if let Some(before) = &config.before
+            && self.created().duration_since(before).as_secs() >= 0
+        {
+            debug!("Skipping due to `before` filter ({before})");
+            return true;
+        }
+        if let Some(after) = &config.after
+            && self.created().duration_since(after).as_secs() <= 0
+        {
+            debug!("Skipping due to `after` filter ({after})");
+            return true;
+        }
+
+        false
+    }
+
+    #[instrument(level = "info", skip(client))]
+    async fn delete(&self, client: &RedditClient, _config: &Config) -> Result<(), ShredditError> {
+        info!("Deleting...");
+
+        client.del_msg(&self.fullname()).await
+    }
+}
+
+/// https://www.reddit.com/dev/api/#GET_message_{where}
+#[instrument(level = "info", skip_all)]
+pub fn list(client: &RedditClient, _config: &Config) -> impl Stream<Item = Message> + use<> {
+    info!("Fetching messages...");
+
+    client
+        .listing("/message/inbox")
+        .chain(client.listing("/message/sent"))
+        .filter(|message: &Message| ready(!message.was_comment()))
+}
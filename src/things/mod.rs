@@ -1,59 +1,161 @@
 pub mod friend;
-pub use friend::*;
+pub use friend::Friend;
 
 pub mod comment;
-pub use comment::*;
+pub use comment::Comment;
 
 pub mod post;
-pub use post::*;
+pub use post::Post;
+
+pub mod message;
+pub use message::Message;
 
 pub mod saved_post;
-pub use saved_post::*;
+pub use saved_post::SavedPost;
+
+pub mod hidden_post;
+pub use hidden_post::HiddenPost;
 
 pub mod saved_comment;
-pub use saved_comment::*;
+pub use saved_comment::SavedComment;
+
+pub mod subscription;
+pub use subscription::Subscription;
+
+pub mod vote;
+pub use vote::{CommentVote, PostVote, Vote};
 
 use clap::ValueEnum;
-use reqwest::Client;
 use serde::Deserialize;
-use std::{collections::HashSet, fmt::Debug, ops::Deref, str::FromStr, time::Duration};
-use tokio::time::sleep;
-use tracing::debug;
-
-use crate::cli::Config;
+use std::{collections::HashSet, fmt::Debug, ops::Deref, str::FromStr};
+use tracing::{debug, error};
+
+use crate::{
+    cli::Config,
+    client::RedditClient,
+    error::ShredditError,
+    replacement::{Placeholders, Replacer},
+};
 use async_trait::async_trait;
+use tokio::time::sleep;
 
-// Reddit has a new rate limit as of 7/1/2023:
-// OAuth for authentication: 100 queries per minute per OAuth client id - sleep atleast 0.6s after every call ( 650 ms)
-// not using OAuth for authentication: 10 queries per minute - must sleep atleast 6s between calls ( 6500 ms)
-const SLEEP_DUR: Duration = Duration::from_millis(2_000); // 2s seems to be the magic number pre-7/1....
-pub async fn prevent_rate_limit() {
-    debug!("Sleeping for {SLEEP_DUR:?} to prevent rate limiting.");
-    sleep(SLEEP_DUR).await;
+/// What shredding a single thing amounted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// A filter excluded the thing, or there was nothing to do with it.
+    Skipped,
+    /// The thing would have been shredded, but this is a dry run.
+    DryRun,
+    /// The thing was overwritten but not deleted, e.g. because of `edit_only`.
+    Edited,
+    /// The thing was deleted, after being overwritten if it can be.
+    Deleted,
 }
 
 #[async_trait]
-pub trait Shred {
-    async fn delete(&self, client: &Client, access_token: &str, config: &Config);
-    async fn edit(&self, _client: &Client, _access_token: &str, _config: &Config) {}
-    async fn prevent_rate_limit(&self) {
-        prevent_rate_limit().await;
+pub trait Shred: Send + Sync {
+    /// The thing's name on Reddit, i.e. its fullname or, for friends and subscriptions, the
+    /// username or subreddit name.
+    fn name(&self) -> String;
+
+    fn should_skip(&self, _config: &Config) -> bool {
+        false
     }
-    async fn shred(&self, client: &Client, access_token: &str, config: &Config) {
-        self.edit(client, access_token, config).await;
-        self.delete(client, access_token, config).await;
+
+    async fn delete(&self, client: &RedditClient, config: &Config) -> Result<(), ShredditError>;
+
+    /// Overwrites the thing's content. Returns whether there was anything to overwrite.
+    async fn edit(
+        &self,
+        _client: &RedditClient,
+        _config: &Config,
+        _replacer: &Replacer,
+    ) -> Result<bool, ShredditError> {
+        Ok(false)
+    }
+
+    async fn shred(
+        &self,
+        client: &RedditClient,
+        config: &Config,
+        replacer: &Replacer,
+    ) -> Result<Outcome, ShredditError> {
+        if self.should_skip(config) {
+            return Ok(Outcome::Skipped);
+        }
+
+        if config.dry_run {
+            return Ok(Outcome::DryRun);
+        }
+
+        let edited = self.edit(client, config, replacer).await;
+
+        if config.should_prevent_deletion() {
+            return match edited? {
+                true => Ok(Outcome::Edited),
+                false => Ok(Outcome::Skipped),
+            };
+        }
+
+        // In two-phase mode, a later run deletes what was overwritten. Things with no text to
+        // overwrite are deleted right away.
+        if let Some(grace) = config.delete_edited_after
+            && let Ok(true) = edited
+        {
+            debug!("Deferring DELETION due to `delete_edited_after` ({grace:?})");
+            return Ok(Outcome::Edited);
+        }
+
+        // A failed edit shouldn't keep us from deleting, unless it failed for a transient reason
+        // and a later run could still overwrite the original text.
+        match edited {
+            Err(e) if e.is_transient() => return Err(e),
+            Err(e) => error!("Couldn't edit: {e}"),
+            Ok(_) => {}
+        }
+
+        self.delete(client, config).await?;
+
+        Ok(Outcome::Deleted)
     }
 }
 
-pub static LOREM_IPSUM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.";
+/// Overwrites the text of a comment or self post once per overwrite pass, waiting the overwrite
+/// delay between passes.
+pub async fn overwrite(
+    client: &RedditClient,
+    config: &Config,
+    replacer: &Replacer,
+    fullname: &str,
+    original: &str,
+    placeholders: &Placeholders<'_>,
+) -> Result<(), ShredditError> {
+    for (pass, text) in config
+        .replacements(replacer, original, placeholders)
+        .iter()
+        .enumerate()
+    {
+        if pass > 0
+            && let Some(delay) = config.overwrite_delay
+        {
+            sleep(delay).await;
+        }
 
-#[derive(Debug)]
-pub enum ShredditError {
-    #[allow(dead_code)]
-    RateLimited,
-    Unknown,
+        client.edit_usertext(fullname, text).await?;
+    }
+
+    Ok(())
+}
+
+/// Takes the subreddit from a permalink like `https://www.reddit.com/r/rust/comments/...`, for
+/// GDPR exports that don't have a subreddit column.
+pub fn subreddit_from_permalink(permalink: &str) -> Option<&str> {
+    let (_, rest) = permalink.split_once("/r/")?;
+    rest.split('/').next()
 }
 
+pub static LOREM_IPSUM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.";
+
 #[derive(Debug, Deserialize, PartialEq, Clone, ValueEnum)]
 pub enum ThingType {
     Posts,
@@ -61,6 +163,10 @@ pub enum ThingType {
     Friends,
     SavedPosts,
     SavedComments,
+    Votes,
+    Messages,
+    Subscriptions,
+    HiddenPosts,
 }
 
 impl FromStr for ThingType {
@@ -73,6 +179,10 @@ impl FromStr for ThingType {
             "friends" => Ok(Self::Friends),
             "saved-posts" => Ok(Self::SavedPosts),
             "saved-comments" => Ok(Self::SavedComments),
+            "votes" => Ok(Self::Votes),
+            "messages" => Ok(Self::Messages),
+            "subscriptions" => Ok(Self::Subscriptions),
+            "hidden-posts" => Ok(Self::HiddenPosts),
             _ => Err("Invalid type"),
         }
     }
@@ -81,10 +191,17 @@ impl FromStr for ThingType {
 pub type SubredditSet = ShredditSet;
 pub type CommentIdSet = ShredditSet;
 pub type PostIdSet = ShredditSet;
+pub type UserSet = ShredditSet;
 
 #[derive(Debug, Clone)]
 pub struct ShredditSet(HashSet<String>);
 
+impl<S: Into<String>> FromIterator<S> for ShredditSet {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Self(iter.into_iter().map(Into::into).collect())
+    }
+}
+
 impl std::convert::From<&str> for ShredditSet {
     fn from(s: &str) -> Self {
         Self(s.split(',').map(Into::into).collect())
@@ -1,29 +1,24 @@
-use super::Shred;
+use super::{Shred, overwrite};
 use crate::{
     cli::Config,
-    sources::{api::Api, gdpr::Gdpr},
+    client::RedditClient,
+    error::ShredditError,
+    replacement::{Placeholders, Replacer},
+    sources::{
+        api::Api,
+        gdpr::{self, Gdpr},
+    },
 };
-use async_stream::stream;
 use async_trait::async_trait;
 use futures_core::Stream;
 use jiff::{Timestamp, Zoned, tz::TimeZone};
-use reqwest::{Client, header::HeaderMap};
 use serde::Deserialize;
 use serde_json::Value;
-use std::collections::HashMap;
-use tracing::{debug, error, info, instrument};
-
-#[derive(Debug, Deserialize)]
-pub struct PostData {
-    data: Post,
-    #[allow(dead_code)]
-    kind: String,
-}
+use tracing::{debug, error, info, instrument, warn};
 
 #[derive(Debug, Deserialize)]
 pub struct Post {
     id: String,
-    #[allow(dead_code)]
     permalink: String,
     #[allow(dead_code)]
     title: String,
@@ -38,16 +33,32 @@ pub struct Post {
 enum Source {
     Api {
         can_gild: bool,
-        created_utc: f32,
+        created_utc: f64,
+        /// Only self posts have text that can be edited, link and media posts don't.
+        is_self: bool,
         selftext: String,
         score: i64,
+        #[serde(default)]
+        gilded: u32,
+        #[serde(default)]
+        all_awardings: Vec<Value>,
+        /// Set to e.g. `moderator` or `admin` if it was distinguished.
+        #[serde(default)]
+        distinguished: Option<String>,
+        /// Archived things can't be edited anymore, only deleted.
+        #[serde(default)]
+        archived: bool,
+        /// `[deleted]` once it was deleted.
+        #[serde(default)]
+        author: Option<String>,
     },
 
     // GDPR cols
     // id,permalink,date,ip,subreddit,gildings,title,url,body
     Gdpr {
         date: Zoned,
-        subreddit: String,
+        #[serde(default, deserialize_with = "gdpr::count")]
+        gildings: u32,
     },
 }
 
@@ -57,6 +68,7 @@ impl Api for Post {
 
 impl Gdpr for Post {
     const FILENAME: &'static str = "posts.csv";
+    const COLUMNS: &'static [&'static str] = &["id", "permalink", "date", "subreddit", "title"];
 }
 
 impl Post {
@@ -70,11 +82,48 @@ impl Post {
         }
     }
 
+    /// Only known for posts from Reddit's API, whose author becomes `[deleted]`.
+    fn is_deleted(&self) -> bool {
+        matches!(&self.source, Source::Api { author: Some(author), .. } if author == "[deleted]")
+    }
+
+    fn placeholders(&self) -> Placeholders<'_> {
+        Placeholders {
+            id: &self.id,
+            subreddit: &self.subreddit,
+            created: self.created(),
+            permalink: &self.permalink,
+        }
+    }
+
     fn fullname(&self) -> String {
         format!("{}_{}", Self::TYPE_ID, self.id)
     }
 
+    async fn to_api(&self, client: &RedditClient) -> Result<Self, ShredditError> {
+        debug!("Getting post from API...");
+
+        client
+            .info(&[self.fullname()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ShredditError::NotFound(self.fullname()))
+    }
+}
+
+#[async_trait]
+impl Shred for Post {
+    fn name(&self) -> String {
+        self.fullname()
+    }
+
     fn should_skip(&self, config: &Config) -> bool {
+        if self.is_deleted() {
+            debug!("Skipping because it was already deleted");
+            return true;
+        }
+
         if let Some(skip_post_ids) = &config.skip_post_ids
             && skip_post_ids.contains(&self.id)
         {
@@ -104,7 +153,23 @@ impl Post {
         }
 
         match &self.source {
-            Source::Api { score, .. } => {
+            Source::Api {
+                score,
+                gilded,
+                all_awardings,
+                distinguished,
+                ..
+            } => {
+                if config.keep_distinguished && distinguished.is_some() {
+                    debug!("Skipping due to `keep_distinguished` filter");
+                    return true;
+                }
+
+                if config.keep_gilded && (*gilded > 0 || !all_awardings.is_empty()) {
+                    debug!("Skipping due to `keep_gilded` filter");
+                    return true;
+                }
+
                 if let Some(max_score) = config.max_score
                     && *score > max_score
                 {
@@ -119,9 +184,19 @@ impl Post {
                     return true;
                 }
             }
-            Source::Gdpr { .. } => {
+            Source::Gdpr { gildings, .. } => {
+                if config.keep_distinguished {
+                    error!("Cannot tell whether this was distinguished without Reddit's API");
+                    return true;
+                }
+
+                if config.keep_gilded && *gildings > 0 {
+                    debug!("Skipping due to `keep_gilded` filter");
+                    return true;
+                }
+
                 if config.max_score.is_some() {
-                    error!("Cannot filter by max score when using GDPR data");
+                    error!("Cannot filter by max score without Reddit's API");
                     return true;
                 }
 
@@ -143,113 +218,80 @@ impl Post {
 
         false
     }
-}
 
-#[async_trait]
-impl Shred for Post {
-    #[instrument(level = "info", skip(client, access_token))]
-    async fn delete(&self, client: &Client, access_token: &str, config: &Config) {
+    #[instrument(level = "info", skip(client))]
+    async fn delete(&self, client: &RedditClient, _config: &Config) -> Result<(), ShredditError> {
         info!("Deleting...");
 
-        if self.should_skip(config) || config.should_prevent_deletion() {
-            return;
-        }
+        client.del(&self.fullname()).await
+    }
 
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            format!("Bearer {access_token}").parse().unwrap(),
-        );
+    #[instrument(level = "debug", skip(client, replacer))]
+    async fn edit(
+        &self,
+        client: &RedditClient,
+        config: &Config,
+        replacer: &Replacer,
+    ) -> Result<bool, ShredditError> {
+        debug!("Editing...");
+
+        let api;
+        let source = match &self.source {
+            Source::Api { .. } => &self.source,
+            Source::Gdpr { .. } => {
+                api = self.to_api(client).await?;
+                &api.source
+            }
+        };
+
+        let Source::Api {
+            is_self,
+            selftext,
+            archived,
+            ..
+        } = source
+        else {
+            unreachable!()
+        };
+
+        if !is_self {
+            debug!("Not editing link post");
+            return Ok(false);
+        }
 
-        headers.insert("User-Agent", config.user_agent.parse().unwrap());
+        if *archived {
+            debug!("Not editing archived post");
+            return Ok(false);
+        }
 
-        let params = HashMap::from([("id", self.fullname())]);
+        if selftext.is_empty() {
+            debug!("Not editing self post without text");
+            return Ok(false);
+        }
 
-        let _res = client
-            .post("https://oauth.reddit.com/api/del")
-            .headers(headers)
-            .form(&params)
-            .send()
-            .await
-            .unwrap();
+        if config.is_replacement(selftext, &self.placeholders()) {
+            debug!("Not editing self post that was already overwritten");
+            return Ok(false);
+        }
 
-        self.prevent_rate_limit().await;
+        overwrite(
+            client,
+            config,
+            replacer,
+            &self.fullname(),
+            selftext,
+            &self.placeholders(),
+        )
+        .await?;
+
+        Ok(true)
     }
 }
 
 /// https://www.reddit.com/dev/api/#GET_user_{username}_submitted
 #[instrument(level = "info", skip_all)]
-pub async fn list(
-    client: &Client,
-    access_token: &str,
-    config: &Config,
-) -> impl Stream<Item = Post> {
+pub fn list(client: &RedditClient, config: &Config) -> impl Stream<Item = Post> + use<> {
     info!("Fetching posts...");
 
-    let client = client.clone();
-    let username = config.username.clone();
-    let user_agent = config.user_agent.clone();
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        format!("Bearer {access_token}").parse().unwrap(),
-    );
-    headers.insert("User-Agent", user_agent.parse().unwrap());
-
-    stream! {
-        let mut last_seen = None;
-
-        loop {
-            let query_params = if let Some(last_seen) = last_seen {
-                format!("?after={last_seen}&limit=100")
-            } else {
-                "?limit=100".to_string()
-            };
-
-            let uri = format!("https://oauth.reddit.com/user/{username}/submitted.json{query_params}");
-
-            let res: PostRes = client
-                    .get(&uri)
-                    .headers(headers.clone())
-                    .send()
-                    .await
-                    .unwrap()
-                    .json().await
-                    .unwrap();
-
-            match res {
-                PostRes::Success { data } => {
-                    let results_len = data.children.len();
-
-                    debug!("Page contained {results_len} results");
-
-                    if results_len == 0 {
-                                break;
-                    } else {
-                                last_seen = data.children.last().map(|t| t.data.fullname());
-                    }
-
-                    for comment in data.children {
-                                yield comment.data;
-                    }
-                }
-                PostRes::Error(e) => {
-                    error!("{e:#?}");
-                    break
-                }
-            }
-        }
-    }
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-enum PostRes {
-    Success { data: PostResData },
-    Error(Value),
-}
-
-#[derive(Debug, Deserialize)]
-pub struct PostResData {
-    pub children: Vec<PostData>,
+    client.listing(&format!("/user/{}/submitted", config.username))
 }
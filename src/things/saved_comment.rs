@@ -1,27 +1,17 @@
-use std::collections::HashMap;
-
-use async_stream::stream;
 use async_trait::async_trait;
 use futures_core::Stream;
-use reqwest::{Client, header::HeaderMap};
 use serde::Deserialize;
-use serde_json::Value;
-use tracing::{debug, error, info, instrument, warn};
+use tracing::{debug, info, instrument};
 
 use crate::{
     cli::Config,
+    client::RedditClient,
+    error::ShredditError,
     sources::{api::Api, gdpr::Gdpr},
 };
 
 use super::Shred;
 
-#[derive(Debug, Deserialize)]
-pub struct SavedCommentData {
-    data: SavedComment,
-    #[allow(dead_code)]
-    kind: String,
-}
-
 #[allow(unused)]
 #[derive(Debug, Deserialize)]
 pub struct SavedComment {
@@ -32,6 +22,7 @@ pub struct SavedComment {
 
 impl Gdpr for SavedComment {
     const FILENAME: &'static str = "saved_comments.csv";
+    const COLUMNS: &'static [&'static str] = &["id", "permalink", "subreddit"];
 }
 
 impl Api for SavedComment {
@@ -46,46 +37,10 @@ impl SavedComment {
 
 #[async_trait]
 impl Shred for SavedComment {
-    #[instrument(level = "info", skip(client, access_token))]
-    async fn delete(&self, client: &Client, access_token: &str, config: &Config) {
-        info!("Deleting...");
-
-        if self.should_skip(config) {
-            return;
-        }
-
-        if config.should_prevent_deletion() {
-            return;
-        }
-
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            format!("Bearer {access_token}").parse().unwrap(),
-        );
-
-        headers.insert("User-Agent", config.user_agent.parse().unwrap());
-
-        let params = HashMap::from([("id", format!("{}_{}", Self::TYPE_ID, self.id))]);
-
-        let res = client
-            .post("https://oauth.reddit.com/api/unsave")
-            .headers(headers)
-            .form(&params)
-            .header("User-Agent", config.user_agent.clone())
-            .send()
-            .await
-            .unwrap();
-
-        if !res.status().is_success() {
-            error!("{:#?}", res.status());
-        }
-
-        self.prevent_rate_limit().await;
+    fn name(&self) -> String {
+        self.fullname()
     }
-}
 
-impl SavedComment {
     fn should_skip(&self, config: &Config) -> bool {
         if let Some(skip_comment_ids) = &config.skip_comment_ids
             && skip_comment_ids.contains(&self.id)
@@ -107,103 +62,19 @@ impl SavedComment {
         }
         false
     }
-}
-
-/// https://www.reddit.com/dev/api/#GET_user_{username}_saved
-#[instrument(level = "info", skip_all)]
-pub async fn list(
-    client: &Client,
-    access_token: &str,
-    config: &Config,
-) -> impl Stream<Item = SavedComment> {
-    info!("Fetching posts...");
-
-    let client = client.clone();
-    let username = config.username.clone();
-    let user_agent = config.user_agent.clone();
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        format!("Bearer {access_token}").parse().unwrap(),
-    );
-
-    headers.insert("User-Agent", user_agent.parse().unwrap());
-
-    stream! {
-    let mut last_seen = None;
-
-        loop {
-    let query_params = if let Some(last_seen) = last_seen {
-        format!("&after={last_seen}")
-    } else {
-        String::new()
-    };
-
-    let uri = format!("https://oauth.reddit.com/user/{username}/saved.json?&type=comments{query_params}");
-
-    let res = client
-                .get(&uri)
-                .headers(headers.clone())
-                .send()
-                .await
-                .unwrap()
-                .json()
-                .await;
-
-    let res = match res {
-        Ok(res) => res,
-        Err(_e) => {
-            warn!("first attempt to list failed");
-
-            let res: SavedCommentRes = client
-                        .get(&uri)
-                        .headers(headers.clone())
-                        .send()
-                        .await
-                        .unwrap()
-                        .json()
-                        .await
-                        .unwrap();
-            res
-        }
-    };
-
-    match res {
-        SavedCommentRes::Success { data } => {
-
-    let results_len = data.children.len();
-
-    debug!("Page contained {results_len} results");
-
-    if results_len == 0 {
-                break;
-    } else {
-                last_seen = data.children.last().map(|t| t.data.fullname());
-    }
 
-    for comment in data.children {
-                yield comment.data;
-    }
-        }
-        SavedCommentRes::Error(e) => {
-    error!("{e:#?}");
-    break
-        }
-
-    }
+    #[instrument(level = "info", skip(client))]
+    async fn delete(&self, client: &RedditClient, _config: &Config) -> Result<(), ShredditError> {
+        info!("Deleting...");
 
-        }
+        client.unsave(&self.fullname()).await
     }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-enum SavedCommentRes {
-    Success { data: SavedCommentResData },
-    Error(Value),
-}
+/// https://www.reddit.com/dev/api/#GET_user_{username}_saved
+#[instrument(level = "info", skip_all)]
+pub fn list(client: &RedditClient, config: &Config) -> impl Stream<Item = SavedComment> + use<> {
+    info!("Fetching saved comments...");
 
-#[derive(Debug, Deserialize)]
-pub struct SavedCommentResData {
-    pub children: Vec<SavedCommentData>,
+    client.listing(&format!("/user/{}/saved?type=comments", config.username))
 }
@@ -1,27 +1,17 @@
-use std::collections::HashMap;
-
-use async_stream::stream;
 use async_trait::async_trait;
 use futures_core::Stream;
-use reqwest::{Client, header::HeaderMap};
 use serde::Deserialize;
-use serde_json::Value;
-use tracing::{debug, error, info, instrument, warn};
+use tracing::{debug, info, instrument};
 
 use crate::{
     cli::Config,
+    client::RedditClient,
+    error::ShredditError,
     sources::{api::Api, gdpr::Gdpr},
 };
 
 use super::Shred;
 
-#[derive(Debug, Deserialize)]
-pub struct SavedPostData {
-    data: SavedPost,
-    #[allow(dead_code)]
-    kind: String,
-}
-
 #[derive(Debug, Deserialize)]
 pub struct SavedPost {
     id: String,
@@ -38,6 +28,7 @@ impl SavedPost {
 
 impl Gdpr for SavedPost {
     const FILENAME: &'static str = "saved_posts.csv";
+    const COLUMNS: &'static [&'static str] = &["id", "permalink", "subreddit"];
 }
 
 impl Api for SavedPost {
@@ -46,164 +37,50 @@ impl Api for SavedPost {
 
 #[async_trait]
 impl Shred for SavedPost {
-    #[instrument(level = "info", skip(client, access_token))]
-    async fn delete(&self, client: &Client, access_token: &str, config: &Config) {
-        info!("Deleting...");
-
-        if self.should_skip(config) {
-            return;
-        }
-
-        if config.should_prevent_deletion() {
-            return;
-        }
-
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            format!("Bearer {access_token}").parse().unwrap(),
-        );
-
-        headers.insert("User-Agent", config.user_agent.parse().unwrap());
-
-        let params = HashMap::from([("id", format!("{}_{}", Self::TYPE_ID, self.id))]);
-
-        let res = client
-            .post("https://oauth.reddit.com/api/unsave")
-            .headers(headers)
-            .form(&params)
-            .header("User-Agent", config.user_agent.clone())
-            .send()
-            .await
-            .unwrap();
-
-        if !res.status().is_success() {
-            error!("{:#?}", res.text().await.unwrap());
-        }
-
-        self.prevent_rate_limit().await;
+    fn name(&self) -> String {
+        self.fullname()
     }
-}
 
-impl SavedPost {
     fn should_skip(&self, config: &Config) -> bool {
-        if let Some(skip_post_ids) = &config.skip_post_ids
-            && skip_post_ids.contains(&self.id)
-        {
-            debug!("Skipping due to `skip_post_ids` filter");
-            return true;
-        }
-        if let Some(skip_subreddits) = &config.skip_subreddits
-            && skip_subreddits.contains(&self.subreddit)
-        {
-            debug!("Skipping due to `skip_subreddits` filter");
-            return true;
-        }
-        if let Some(only_subreddits) = &config.only_subreddits
-            && !only_subreddits.contains(&self.subreddit)
-        {
-            debug!("Skipping due to `only_subreddits` filter");
-            return true;
-        }
-        false
+        should_skip_post(config, &self.id, Some(&self.subreddit))
     }
-}
-
-/// https://www.reddit.com/dev/api/#GET_user_{username}_saved
-#[instrument(level = "info", skip_all)]
-pub async fn list(
-    client: &Client,
-    access_token: &str,
-    config: &Config,
-) -> impl Stream<Item = SavedPost> {
-    info!("Fetching posts...");
 
-    let client = client.clone();
-    let username = config.username.clone();
-    let user_agent = config.user_agent.clone();
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        format!("Bearer {access_token}").parse().unwrap(),
-    );
-
-    headers.insert("User-Agent", user_agent.parse().unwrap());
-
-    stream! {
-    let mut last_seen = None;
-
-        loop {
-    let query_params = if let Some(last_seen) = last_seen {
-        format!("&after={last_seen}")
-    } else {
-        String::new()
-    };
-
-    let uri = format!("https://oauth.reddit.com/user/{username}/saved.json?&type=links{query_params}");
-
-    let res = client
-                .get(&uri)
-                .headers(headers.clone())
-                .send()
-                .await
-                .unwrap()
-                .json()
-                .await;
-
-    let res = match res {
-        Ok(res) => res,
-        Err(_e) => {
-            warn!("first attempt to list failed");
-
-            let res: SavedPostRes = client
-                        .get(&uri)
-                        .headers(headers.clone())
-                        .send()
-                        .await
-                        .unwrap()
-                        .json()
-                        .await
-                        .unwrap();
-            res
-        }
-    };
-
-    match res {
-        SavedPostRes::Success { data } => {
-
-    let results_len = data.children.len();
-
-    debug!("Page contained {results_len} results");
+    #[instrument(level = "info", skip(client))]
+    async fn delete(&self, client: &RedditClient, _config: &Config) -> Result<(), ShredditError> {
+        info!("Deleting...");
 
-    if results_len == 0 {
-                break;
-    } else {
-                last_seen = data.children.last().map(|t| t.data.fullname());
+        client.unsave(&self.fullname()).await
     }
+}
 
-    for comment in data.children {
-                yield comment.data;
+/// The filters for saved and hidden posts. Posts whose subreddit isn't known are kept when
+/// `only_subreddits` is set.
+pub fn should_skip_post(config: &Config, id: &str, subreddit: Option<&str>) -> bool {
+    if let Some(skip_post_ids) = &config.skip_post_ids
+        && skip_post_ids.contains(id)
+    {
+        debug!("Skipping due to `skip_post_ids` filter");
+        return true;
     }
-        }
-        SavedPostRes::Error(e) => {
-    error!("{e:#?}");
-    break
-        }
-
+    if let Some(skip_subreddits) = &config.skip_subreddits
+        && subreddit.is_some_and(|s| skip_subreddits.contains(s))
+    {
+        debug!("Skipping due to `skip_subreddits` filter");
+        return true;
     }
-
-        }
+    if let Some(only_subreddits) = &config.only_subreddits
+        && !subreddit.is_some_and(|s| only_subreddits.contains(s))
+    {
+        debug!("Skipping due to `only_subreddits` filter");
+        return true;
     }
+    false
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-enum SavedPostRes {
-    Success { data: SavedPostResData },
-    Error(Value),
-}
+/// https://www.reddit.com/dev/api/#GET_user_{username}_saved
+#[instrument(level = "info", skip_all)]
+pub fn list(client: &RedditClient, config: &Config) -> impl Stream<Item = SavedPost> + use<> {
+    info!("Fetching saved posts...");
 
-#[derive(Debug, Deserialize)]
-pub struct SavedPostResData {
-    pub children: Vec<SavedPostData>,
+    client.listing(&format!("/user/{}/saved?type=links", config.username))
 }
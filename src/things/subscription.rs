@@ -0,0 +1,57 @@
+use super::Shred;
+use crate::{cli::Config, client::RedditClient, error::ShredditError, sources::gdpr::Gdpr};
+use async_trait::async_trait;
+use futures_core::Stream;
+use serde::Deserialize;
+use tracing::{debug, info, instrument};
+
+#[derive(Debug, Deserialize)]
+pub struct Subscription {
+    /// Reddit's API calls this `display_name`, GDPR exports call it `subreddit`.
+    #[serde(alias = "display_name")]
+    subreddit: String,
+}
+
+impl Gdpr for Subscription {
+    const FILENAME: &'static str = "subscribed_subreddits.csv";
+    const COLUMNS: &'static [&'static str] = &["subreddit"];
+}
+
+#[async_trait]
+impl Shred for Subscription {
+    fn name(&self) -> String {
+        self.subreddit.clone()
+    }
+
+    fn should_skip(&self, config: &Config) -> bool {
+        if let Some(skip_subreddits) = &config.skip_subreddits
+            && skip_subreddits.contains(&self.subreddit)
+        {
+            debug!("Skipping due to `skip_subreddits` filter");
+            return true;
+        }
+        if let Some(only_subreddits) = &config.only_subreddits
+            && !only_subreddits.contains(&self.subreddit)
+        {
+            debug!("Skipping due to `only_subreddits` filter");
+            return true;
+        }
+
+        false
+    }
+
+    #[instrument(level = "info", skip(client))]
+    async fn delete(&self, client: &RedditClient, _config: &Config) -> Result<(), ShredditError> {
+        info!("Unsubscribing...");
+
+        client.unsubscribe(&self.subreddit).await
+    }
+}
+
+/// https://www.reddit.com/dev/api/#GET_subreddits_mine_{where}
+#[instrument(level = "info", skip_all)]
+pub fn list(client: &RedditClient, _config: &Config) -> impl Stream<Item = Subscription> + use<> {
+    info!("Fetching subscriptions...");
+
+    client.listing("/subreddits/mine/subscriber")
+}
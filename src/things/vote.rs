@@ -0,0 +1,129 @@
+use super::{Comment, Post, Shred, subreddit_from_permalink};
+use crate::{
+    cli::Config,
+    client::RedditClient,
+    error::ShredditError,
+    sources::{api::Api, gdpr::Gdpr},
+};
+use async_trait::async_trait;
+use futures_core::Stream;
+use futures_util::StreamExt;
+use jiff::{Timestamp, Zoned, tz::TimeZone};
+use serde::Deserialize;
+use std::{fmt::Debug, marker::PhantomData};
+use tracing::{debug, error, info, instrument};
+
+/// Your vote on a post or comment. Votes can't be deleted, so shredding one clears it instead.
+#[derive(Debug, Deserialize)]
+pub struct Vote<T> {
+    id: String,
+    permalink: String,
+    /// When the voted thing was created. Only Reddit's API has this, GDPR exports don't.
+    created_utc: Option<f64>,
+    #[serde(skip)]
+    thing: PhantomData<T>,
+}
+
+pub type PostVote = Vote<Post>;
+pub type CommentVote = Vote<Comment>;
+
+// GDPR columns
+// id,permalink,direction
+impl Gdpr for PostVote {
+    const FILENAME: &'static str = "post_votes.csv";
+    const COLUMNS: &'static [&'static str] = &["id", "permalink"];
+}
+
+impl Gdpr for CommentVote {
+    const FILENAME: &'static str = "comment_votes.csv";
+    const COLUMNS: &'static [&'static str] = &["id", "permalink"];
+}
+
+impl<T: Api> Vote<T> {
+    fn fullname(&self) -> String {
+        format!("{}_{}", T::TYPE_ID, self.id)
+    }
+
+    /// Neither source has a subreddit column, so this is taken from the permalink.
+    fn subreddit(&self) -> Option<&str> {
+        subreddit_from_permalink(&self.permalink)
+    }
+
+    fn created(&self) -> Option<Zoned> {
+        let created_utc = self.created_utc?;
+        let timestamp = Timestamp::from_second(created_utc as i64).ok()?;
+
+        Some(timestamp.to_zoned(TimeZone::UTC))
+    }
+}
+
+#[async_trait]
+impl<T> Shred for Vote<T>
+where
+    T: Api + Debug + Send + Sync,
+{
+    fn name(&self) -> String {
+        self.fullname()
+    }
+
+    fn should_skip(&self, config: &Config) -> bool {
+        if let Some(skip_subreddits) = &config.skip_subreddits
+            && self
+                .subreddit()
+                .is_some_and(|s| skip_subreddits.contains(s))
+        {
+            debug!("Skipping due to `skip_subreddits` filter");
+            return true;
+        }
+        if let Some(only_subreddits) = &config.only_subreddits
+            && !self
+                .subreddit()
+                .is_some_and(|s| only_subreddits.contains(s))
+        {
+            debug!("Skipping due to `only_subreddits` filter");
+            return true;
+        }
+        if config.before.is_none() && config.after.is_none() {
+            return false;
+        }
+
+        let Some(created) = self.created() else {
+            error!("Cannot filter votes by date when using GDPR data");
+            return true;
+        };
+
+        if let Some(before) = &config.before
+            && created.duration_since(before).as_secs() >= 0
+        {
+            debug!("Skipping due to `before` filter ({before})");
+            return true;
+        }
+        if let Some(after) = &config.after
+            && created.duration_since(after).as_secs() <= 0
+        {
+            debug!("Skipping due to `after` filter ({after})");
+            return true;
+        }
+
+        false
+    }
+
+    #[instrument(level = "info", skip(client))]
+    async fn delete(&self, client: &RedditClient, _config: &Config) -> Result<(), ShredditError> {
+        info!("Clearing vote...");
+
+        client.clear_vote(&self.fullname()).await
+    }
+}
+
+/// https://www.reddit.com/dev/api/#GET_user_{username}_upvoted
+///
+/// Reddit only lists votes on posts, so votes on comments can only be cleared using a GDPR export.
+#[instrument(level = "info", skip_all)]
+pub fn list(client: &RedditClient, config: &Config) -> impl Stream<Item = PostVote> + use<> {
+    info!("Fetching votes...");
+
+    client
+        .listing(&format!("/user/{}/upvoted", config.username))
+        .chain(client.listing(&format!("/user/{}/downvoted", config.username)))
+}
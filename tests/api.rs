@@ -0,0 +1,617 @@
+mod fake_reddit;
+
+use fake_reddit::{FakeReddit, Mutation, USERNAME, comment, link_post, message, post, run};
+
+fn edit(thing_id: &str, text: &str) -> Mutation {
+    Mutation::Edit {
+        thing_id: thing_id.to_string(),
+        text: text.to_string(),
+    }
+}
+
+fn del(fullname: &str) -> Mutation {
+    Mutation::Del(fullname.to_string())
+}
+
+#[test]
+fn edits_then_deletes_every_page_of_comments() {
+    let reddit = FakeReddit::new()
+        .comments([
+            comment("c1", "rust", "first"),
+            comment("c2", "rust", "second"),
+            comment("c3", "programming", "third"),
+        ])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"]));
+
+    assert_eq!(
+        reddit.mutations(),
+        [
+            edit("t1_c1", "[removed]"),
+            del("t1_c1"),
+            edit("t1_c2", "[removed]"),
+            del("t1_c2"),
+            edit("t1_c3", "[removed]"),
+            del("t1_c3"),
+        ]
+    );
+    assert_eq!(reddit.token_requests(), 1);
+}
+
+#[test]
+fn refreshes_access_tokens_before_they_expire() {
+    // Tokens are refreshed 5 minutes before they expire, so these need refreshing right away.
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "first")])
+        .token_lifetime(300)
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"]));
+
+    assert_eq!(
+        reddit.mutations(),
+        [edit("t1_c1", "[removed]"), del("t1_c1")]
+    );
+    // One token to authenticate, then one for each of the listing, edit and delete requests.
+    assert_eq!(reddit.token_requests(), 4);
+}
+
+#[test]
+fn retries_once_with_a_new_token_after_a_401() {
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "first")])
+        .reject_first_token()
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"]));
+
+    assert_eq!(
+        reddit.mutations(),
+        [edit("t1_c1", "[removed]"), del("t1_c1")]
+    );
+    assert_eq!(reddit.token_requests(), 2);
+}
+
+#[test]
+fn deletes_posts_and_unsaves_saved_things() {
+    let reddit = FakeReddit::new()
+        .posts([post("p1", "rust", "text"), link_post("p2", "rust")])
+        .saved_posts([post("p3", "rust", "")])
+        .saved_comments([comment("c1", "rust", "saved")])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "posts,saved-posts,saved-comments"])
+        .args(["--replacement-comment", "[removed]"]));
+
+    assert_eq!(
+        reddit.mutations(),
+        [
+            edit("t3_p1", "[removed]"),
+            del("t3_p1"),
+            del("t3_p2"),
+            Mutation::Unsave("t3_p3".to_string()),
+            Mutation::Unsave("t1_c1".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn skip_filters_are_respected() {
+    let reddit = FakeReddit::new()
+        .comments([
+            comment("c1", "rust", "first"),
+            comment("c2", "work", "second"),
+            comment("c3", "rust", "third"),
+        ])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .args(["--skip-subreddits", "work"])
+        .args(["--skip-comment-ids", "c3"]));
+
+    assert_eq!(
+        reddit.mutations(),
+        [edit("t1_c1", "[removed]"), del("t1_c1")]
+    );
+}
+
+#[test]
+fn dry_run_changes_nothing() {
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "first")])
+        .posts([post("p1", "rust", "text")])
+        .start();
+
+    run(reddit.shreddit().arg("--dry-run"));
+
+    assert_eq!(reddit.mutations(), []);
+}
+
+#[test]
+fn clears_upvotes_and_downvotes() {
+    let reddit = FakeReddit::new()
+        .upvoted([post("p1", "rust", ""), post("p2", "work", "")])
+        .downvoted([post("p3", "rust", "")])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "votes"])
+        .args(["--skip-subreddits", "work"]));
+
+    let clear_vote = |id: &str| Mutation::Vote {
+        id: id.to_string(),
+        dir: 0,
+    };
+    assert_eq!(
+        reddit.mutations(),
+        [clear_vote("t3_p1"), clear_vote("t3_p3")]
+    );
+}
+
+#[test]
+fn deletes_inbox_and_sent_messages() {
+    let mut reply = comment("c1", "rust", "a reply");
+    reply["was_comment"] = true.into();
+    reply["author"] = "someone".into();
+    reply["dest"] = USERNAME.into();
+
+    let reddit = FakeReddit::new()
+        .inbox([
+            message("m1", "someone", USERNAME),
+            reply,
+            message("m2", "Friend", USERNAME),
+        ])
+        .sent([message("m3", USERNAME, "someone")])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "messages"])
+        .args(["--skip-message-users", "friend"]));
+
+    assert_eq!(
+        reddit.mutations(),
+        [
+            Mutation::DelMsg("t4_m1".to_string()),
+            Mutation::DelMsg("t4_m3".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn unfriends_all_but_skipped_friends() {
+    let reddit = FakeReddit::new().friends(["alice", "Bob", "carol"]).start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "friends"])
+        .args(["--skip-friends", "bob"]));
+
+    assert_eq!(
+        reddit.mutations(),
+        [
+            Mutation::Unfriend("alice".to_string()),
+            Mutation::Unfriend("carol".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn unsubscribes_from_all_but_skipped_subreddits() {
+    let reddit = FakeReddit::new()
+        .subscriptions(["rust", "work", "programming"])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "subscriptions"])
+        .args(["--skip-subreddits", "work"]));
+
+    assert_eq!(
+        reddit.mutations(),
+        [
+            Mutation::Unsubscribe("rust".to_string()),
+            Mutation::Unsubscribe("programming".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn unhides_hidden_posts() {
+    let reddit = FakeReddit::new()
+        .hidden([
+            post("p1", "rust", ""),
+            post("p2", "work", ""),
+            post("p3", "rust", ""),
+        ])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "hidden-posts"])
+        .args(["--skip-subreddits", "work"])
+        .args(["--skip-post-ids", "p3"]));
+
+    assert_eq!(reddit.mutations(), [Mutation::Unhide("t3_p1".to_string())]);
+}
+
+#[test]
+fn edit_only_overwrites_self_posts() {
+    let reddit = FakeReddit::new()
+        .posts([
+            post("p1", "rust", "text"),
+            link_post("p2", "rust"),
+            post("p3", "rust", ""),
+        ])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "posts"])
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--edit-only"));
+
+    assert_eq!(reddit.mutations(), [edit("t3_p1", "[removed]")]);
+}
+
+#[test]
+fn keep_gilded_keeps_gilded_and_awarded_things() {
+    let mut gilded = comment("c2", "rust", "gilded");
+    gilded["gilded"] = 1.into();
+
+    let mut awarded = post("p2", "rust", "");
+    awarded["all_awardings"] = serde_json::json!([{ "name": "Helpful" }]);
+
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "plain"), gilded])
+        .posts([post("p1", "rust", ""), awarded])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--keep-gilded"));
+
+    assert_eq!(
+        reddit.mutations(),
+        [del("t3_p1"), edit("t1_c1", "[removed]"), del("t1_c1")]
+    );
+}
+
+#[test]
+fn keep_distinguished_keeps_distinguished_things() {
+    let mut distinguished = comment("c2", "rust", "mod note");
+    distinguished["distinguished"] = "moderator".into();
+
+    let mut stickied = post("p2", "rust", "");
+    stickied["distinguished"] = "admin".into();
+
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "plain"), distinguished])
+        .posts([post("p1", "rust", ""), stickied])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--keep-distinguished"));
+
+    assert_eq!(
+        reddit.mutations(),
+        [del("t3_p1"), edit("t1_c1", "[removed]"), del("t1_c1")]
+    );
+}
+
+#[test]
+fn archived_things_are_deleted_without_editing() {
+    let mut archived = comment("c2", "rust", "old");
+    archived["archived"] = true.into();
+
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "new"), archived])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"]));
+
+    assert_eq!(
+        reddit.mutations(),
+        [edit("t1_c1", "[removed]"), del("t1_c1"), del("t1_c2")]
+    );
+}
+
+#[test]
+fn deletes_self_posts_already_overwritten_without_editing() {
+    let reddit = FakeReddit::new()
+        .posts([post("p1", "rust", "[removed]"), post("p2", "rust", "text")])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "posts"])
+        .args(["--replacement-comment", "[removed]"]));
+
+    assert_eq!(
+        reddit.mutations(),
+        [del("t3_p1"), edit("t3_p2", "[removed]"), del("t3_p2")]
+    );
+}
+
+#[test]
+fn match_length_overwrites_with_fresh_text_of_the_same_length() {
+    let reddit = FakeReddit::new()
+        .comments([
+            comment("c1", "rust", "a short comment"),
+            comment("c2", "rust", "a somewhat longer comment than the first one"),
+        ])
+        .start();
+
+    let shred = || {
+        run(reddit
+            .shreddit()
+            .args(["--thing-types", "comments"])
+            .args(["--replacement-strategy", "match-length"])
+            .args(["--replacement-seed", "42"])
+            .arg("--edit-only"));
+
+        reddit
+            .mutations()
+            .into_iter()
+            .filter_map(|mutation| match mutation {
+                Mutation::Edit { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let first_run = shred();
+    assert_eq!(first_run.len(), 2);
+    assert_eq!(first_run[0].len(), "a short comment".len());
+    assert_eq!(
+        first_run[1].len(),
+        "a somewhat longer comment than the first one".len()
+    );
+
+    // The same seed generates the same text again.
+    let both_runs = shred();
+    assert_eq!(both_runs[2..], first_run[..]);
+}
+
+#[test]
+fn replacement_comment_placeholders_are_filled_in() {
+    let mut c1 = comment("c1", "rust", "first");
+    c1["created_utc"] = 1_700_000_063.0.into();
+
+    let reddit = FakeReddit::new().comments([c1]).start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args([
+            "--replacement-comment",
+            "{id} in r/{subreddit} from {created}, see {permalink}",
+        ])
+        .arg("--edit-only"));
+
+    assert_eq!(
+        reddit.mutations(),
+        [edit(
+            "t1_c1",
+            "c1 in r/rust from 2023-11-14 22:14:23 UTC, see https://www.reddit.com/r/rust/comments/abc/post/c1/"
+        )]
+    );
+}
+
+#[test]
+fn replacement_file_phrases_are_used_in_turn() {
+    let file = std::env::temp_dir().join(format!("shreddit-phrases-{}.txt", std::process::id()));
+    std::fs::write(&file, "Nothing to see here\nMoved on from r/{subreddit}\n").unwrap();
+
+    let reddit = FakeReddit::new()
+        .comments([
+            comment("c1", "rust", "first"),
+            comment("c2", "rust", "second"),
+            comment("c3", "rust", "third"),
+        ])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .arg("--replacement-file")
+        .arg(&file)
+        .args(["--replacement-order", "round-robin"])
+        .arg("--edit-only"));
+
+    assert_eq!(
+        reddit.mutations(),
+        [
+            edit("t1_c1", "Nothing to see here"),
+            edit("t1_c2", "Moved on from r/rust"),
+            edit("t1_c3", "Nothing to see here"),
+        ]
+    );
+}
+
+#[test]
+fn overwrite_passes_overwrite_with_different_text_before_deleting() {
+    let body = "something I'd rather not have archived";
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", body)])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .args(["--overwrite-passes", "3"])
+        .args(["--overwrite-delay", "10ms"]));
+
+    let mutations = reddit.mutations();
+    let texts: Vec<&str> = mutations
+        .iter()
+        .filter_map(|mutation| match mutation {
+            Mutation::Edit { thing_id, text } if thing_id == "t1_c1" => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(texts.len(), 3);
+    assert_ne!(texts[0], texts[1]);
+    assert!(texts[..2].iter().all(|text| text.len() == body.len()));
+    assert_eq!(texts[2], "[removed]");
+    assert_eq!(mutations.last(), Some(&del("t1_c1")));
+}
+
+#[test]
+fn two_phase_mode_deletes_overwritten_things_in_a_later_run() {
+    let edited_file =
+        std::env::temp_dir().join(format!("shreddit-edited-{}.csv", std::process::id()));
+    let _ = std::fs::remove_file(&edited_file);
+
+    let two_phase = |reddit: &fake_reddit::RunningReddit| {
+        run(reddit
+            .shreddit()
+            .args(["--thing-types", "comments,posts"])
+            .args(["--replacement-comment", "[removed]"])
+            .args(["--delete-edited-after", "1s"])
+            .arg("--edited-file")
+            .arg(&edited_file));
+    };
+    let overwritten = || {
+        [
+            comment("c1", "rust", "first"),
+            comment("c2", "rust", "second"),
+        ]
+    };
+
+    let mut archived = comment("c3", "rust", "old");
+    archived["archived"] = true.into();
+
+    // The first run only overwrites, but deletes what it has no text to overwrite right away.
+    let reddit = FakeReddit::new()
+        .comments(overwritten().into_iter().chain([archived]))
+        .posts([link_post("p1", "rust")])
+        .start();
+    two_phase(&reddit);
+    assert_eq!(
+        reddit.mutations(),
+        [
+            edit("t1_c1", "[removed]"),
+            edit("t1_c2", "[removed]"),
+            del("t1_c3"),
+            del("t3_p1"),
+        ]
+    );
+
+    // The next run doesn't touch them before they're due.
+    let reddit = FakeReddit::new().comments(overwritten()).start();
+    two_phase(&reddit);
+    assert_eq!(reddit.mutations(), []);
+
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    // Once they're deleted, Reddit no longer lists them.
+    let reddit = FakeReddit::new().start();
+    two_phase(&reddit);
+    assert_eq!(reddit.mutations(), [del("t1_c1"), del("t1_c2")]);
+
+    two_phase(&reddit);
+    assert_eq!(reddit.mutations(), [del("t1_c1"), del("t1_c2")]);
+}
+
+#[test]
+fn two_phase_mode_fails_if_the_edited_file_cant_be_read() {
+    // A directory can't be read as the edited file.
+    let edited_file =
+        std::env::temp_dir().join(format!("shreddit-unreadable-{}", std::process::id()));
+    std::fs::create_dir_all(&edited_file).unwrap();
+
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "first")])
+        .start();
+
+    let output = reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--delete-edited-after", "1s"])
+        .arg("--edited-file")
+        .arg(&edited_file)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(reddit.mutations(), []);
+}
+
+#[test]
+fn rate_limited_edits_are_retried_before_deleting() {
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "first")])
+        .rate_limit_edits(1)
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .args(["--retry-delay", "10ms"]));
+
+    assert_eq!(
+        reddit.mutations(),
+        [edit("t1_c1", "[removed]"), del("t1_c1")]
+    );
+}
+
+#[test]
+fn rate_limited_edits_that_keep_failing_are_not_deleted() {
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "first")])
+        .rate_limit_edits(2)
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .args(["--max-attempts", "2"])
+        .args(["--retry-delay", "10ms"]));
+
+    assert_eq!(reddit.mutations(), []);
+}
+
+#[test]
+fn comments_overwritten_on_an_earlier_run_are_not_edited_again() {
+    let reddit = FakeReddit::new()
+        .comments([comment(
+            "c1",
+            "rust",
+            "Content removed on 2020-01-01 00:00:00 UTC, see our retention policy",
+        )])
+        .start();
+
+    run(reddit.shreddit().args(["--thing-types", "comments"]).args([
+        "--replacement-comment",
+        "Content removed on {now}, see our retention policy",
+    ]));
+
+    assert_eq!(reddit.mutations(), [del("t1_c1")]);
+}
@@ -0,0 +1,655 @@
+//! A fake Reddit API for integration tests. It serves the endpoints `shreddit` uses from
+//! in-memory listings and records every mutation so tests can assert on them.
+
+#![allow(dead_code)]
+
+use axum::{
+    Form, Json, Router,
+    extract::{Path, Query, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header::AUTHORIZATION},
+    middleware::{self, Next, map_response},
+    response::Response,
+    routing::{self, delete, get},
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    net::TcpListener,
+    process::{Command, Output},
+    sync::{Arc, Mutex},
+};
+
+pub const USERNAME: &str = "shreddit-test";
+const ACCESS_TOKEN: &str = "fake-access-token";
+
+/// Listings are served a few things at a time so tests exercise pagination.
+const PAGE_SIZE: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mutation {
+    Del(String),
+    Edit { thing_id: String, text: String },
+    Unsave(String),
+    Unfriend(String),
+    Vote { id: String, dir: i8 },
+    DelMsg(String),
+    Unsubscribe(String),
+    Unhide(String),
+}
+
+#[derive(Debug, Default)]
+struct Reddit {
+    /// Things by listing, e.g. `comments` or `saved?type=links`.
+    listings: HashMap<String, Vec<Value>>,
+    mutations: Vec<Mutation>,
+    token_requests: usize,
+    /// How long issued access tokens are valid for, in seconds. Defaults to a day.
+    token_lifetime: Option<u64>,
+    /// Whether to answer requests with the first access token with a 401, as if it expired early.
+    reject_first_token: bool,
+    rejected_tokens: Vec<String>,
+    /// How many edits to answer with a `RATELIMIT` error instead of making them.
+    rate_limited_edits: usize,
+    /// How many fullnames each `api/info` request looked up.
+    info_requests: Vec<usize>,
+}
+
+type SharedReddit = Arc<Mutex<Reddit>>;
+
+#[derive(Debug, Default)]
+pub struct FakeReddit {
+    reddit: Reddit,
+}
+
+pub struct RunningReddit {
+    url: String,
+    reddit: SharedReddit,
+}
+
+impl FakeReddit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds things to a listing, e.g. `comments`, `saved?type=links` or `message/inbox`. Listings
+    /// without a `/` are the user's own.
+    pub fn listing(mut self, name: &str, things: impl IntoIterator<Item = Value>) -> Self {
+        self.reddit
+            .listings
+            .entry(name.to_string())
+            .or_default()
+            .extend(things);
+        self
+    }
+
+    /// Issues access tokens that expire after `seconds`.
+    pub fn token_lifetime(mut self, seconds: u64) -> Self {
+        self.reddit.token_lifetime = Some(seconds);
+        self
+    }
+
+    /// Rejects requests made with the first access token, as if it expired early.
+    pub fn reject_first_token(mut self) -> Self {
+        self.reddit.reject_first_token = true;
+        self
+    }
+
+    /// Answers the next `count` edits with the `RATELIMIT` error Reddit reports in its `jquery`.
+    pub fn rate_limit_edits(mut self, count: usize) -> Self {
+        self.reddit.rate_limited_edits = count;
+        self
+    }
+
+    pub fn comments(self, things: impl IntoIterator<Item = Value>) -> Self {
+        self.listing("comments", things)
+    }
+
+    pub fn posts(self, things: impl IntoIterator<Item = Value>) -> Self {
+        self.listing("submitted", things)
+    }
+
+    pub fn saved_posts(self, things: impl IntoIterator<Item = Value>) -> Self {
+        self.listing("saved?type=links", things)
+    }
+
+    pub fn saved_comments(self, things: impl IntoIterator<Item = Value>) -> Self {
+        self.listing("saved?type=comments", things)
+    }
+
+    pub fn hidden(self, things: impl IntoIterator<Item = Value>) -> Self {
+        self.listing("hidden", things)
+    }
+
+    pub fn upvoted(self, things: impl IntoIterator<Item = Value>) -> Self {
+        self.listing("upvoted", things)
+    }
+
+    pub fn downvoted(self, things: impl IntoIterator<Item = Value>) -> Self {
+        self.listing("downvoted", things)
+    }
+
+    pub fn friends<'a>(self, usernames: impl IntoIterator<Item = &'a str>) -> Self {
+        let friends = usernames.into_iter().map(
+            |name| json!({ "name": name, "id": format!("t2_{name}"), "date": 1_700_000_000.0 }),
+        );
+
+        self.listing("friends", friends)
+    }
+
+    pub fn subscriptions<'a>(self, subreddits: impl IntoIterator<Item = &'a str>) -> Self {
+        let subreddits = subreddits.into_iter().map(|subreddit| {
+            json!({
+                "name": format!("t5_{subreddit}"),
+                "display_name": subreddit,
+                "url": format!("/r/{subreddit}/"),
+            })
+        });
+
+        self.listing("subreddits/mine/subscriber", subreddits)
+    }
+
+    pub fn inbox(self, things: impl IntoIterator<Item = Value>) -> Self {
+        self.listing("message/inbox", things)
+    }
+
+    pub fn sent(self, things: impl IntoIterator<Item = Value>) -> Self {
+        self.listing("message/sent", things)
+    }
+
+    /// Serves the fake API on a random local port from a background thread.
+    pub fn start(self) -> RunningReddit {
+        let reddit = Arc::new(Mutex::new(self.reddit));
+
+        let app = Router::new()
+            .route("/api/v1/access_token", routing::post(access_token))
+            .route("/user/{username}/{listing}", get(user_listing))
+            .route("/message/{listing}", get(message_listing))
+            .route("/subreddits/mine/{listing}", get(subreddit_listing))
+            .route("/api/info", get(info))
+            .route("/api/del", routing::post(del))
+            .route("/api/editusertext", routing::post(edit_usertext))
+            .route("/api/unsave", routing::post(unsave))
+            .route("/api/unhide", routing::post(unhide))
+            .route("/api/vote", routing::post(vote))
+            .route("/api/del_msg", routing::post(del_msg))
+            .route("/api/subscribe", routing::post(subscribe))
+            .route("/api/v1/me/friends", get(friends))
+            .route("/api/v1/me/friends/{username}", delete(unfriend))
+            .layer(middleware::from_fn_with_state(
+                reddit.clone(),
+                reject_tokens,
+            ))
+            .layer(map_response(rate_limit_headers))
+            .with_state(reddit.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                    axum::serve(listener, app).await.unwrap();
+                });
+        });
+
+        RunningReddit { url, reddit }
+    }
+}
+
+impl RunningReddit {
+    /// A `shreddit` command pointed at this fake, with credentials filled in.
+    pub fn shreddit(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_shreddit"));
+
+        // Only drop shreddit's own settings, as e.g. Windows needs `SYSTEMROOT` for networking.
+        for (key, _) in std::env::vars_os() {
+            if key.to_string_lossy().starts_with("SHREDDIT_") {
+                command.env_remove(key);
+            }
+        }
+
+        command
+            .current_dir(std::env::temp_dir())
+            .args(["--username", USERNAME])
+            .args(["--password", "hunter2"])
+            .args(["--client-id", "client-id"])
+            .args(["--client-secret", "client-secret"])
+            .args(["--api-url", &self.url])
+            .args(["--auth-url", &self.url]);
+        command
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn mutations(&self) -> Vec<Mutation> {
+        self.reddit.lock().unwrap().mutations.clone()
+    }
+
+    pub fn token_requests(&self) -> usize {
+        self.reddit.lock().unwrap().token_requests
+    }
+
+    pub fn info_requests(&self) -> Vec<usize> {
+        self.reddit.lock().unwrap().info_requests.clone()
+    }
+}
+
+/// Runs a command to completion, failing the test with its output if it didn't succeed.
+pub fn run(command: &mut Command) -> Output {
+    let output = command.output().unwrap();
+
+    assert!(
+        output.status.success(),
+        "shreddit failed with {}\n{}\n{}",
+        output.status,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    output
+}
+
+/// A comment as returned by Reddit's API.
+pub fn comment(id: &str, subreddit: &str, body: &str) -> Value {
+    json!({
+        "id": id,
+        "name": format!("t1_{id}"),
+        "body": body,
+        "permalink": format!("/r/{subreddit}/comments/abc/post/{id}/"),
+        "subreddit": subreddit,
+        "score": 1,
+        "created_utc": 1_700_000_000.0,
+        "can_gild": true,
+    })
+}
+
+/// A self post as returned by Reddit's API.
+pub fn post(id: &str, subreddit: &str, selftext: &str) -> Value {
+    json!({
+        "id": id,
+        "name": format!("t3_{id}"),
+        "title": format!("Post {id}"),
+        "is_self": true,
+        "selftext": selftext,
+        "permalink": format!("/r/{subreddit}/comments/{id}/post/"),
+        "subreddit": subreddit,
+        "score": 1,
+        "created_utc": 1_700_000_000.0,
+        "can_gild": true,
+    })
+}
+
+/// A link post as returned by Reddit's API.
+pub fn link_post(id: &str, subreddit: &str) -> Value {
+    let mut post = post(id, subreddit, "");
+    post["is_self"] = false.into();
+    post["url"] = "https://example.com/".into();
+    post
+}
+
+/// A private message as returned by Reddit's API.
+pub fn message(id: &str, author: &str, dest: &str) -> Value {
+    json!({
+        "id": id,
+        "name": format!("t4_{id}"),
+        "author": author,
+        "dest": dest,
+        "subreddit": null,
+        "subject": "Hi",
+        "body": "Hello",
+        "created_utc": 1_700_000_000.0,
+        "was_comment": false,
+    })
+}
+
+async fn rate_limit_headers(mut res: Response) -> Response {
+    let headers = res.headers_mut();
+    headers.insert("x-ratelimit-used", HeaderValue::from_static("1"));
+    headers.insert("x-ratelimit-remaining", HeaderValue::from_static("999.0"));
+    headers.insert("x-ratelimit-reset", HeaderValue::from_static("1"));
+    res
+}
+
+fn authorize(headers: &HeaderMap) -> Result<(), StatusCode> {
+    match bearer_token(headers) {
+        Some(token) if token.starts_with(ACCESS_TOKEN) => Ok(()),
+        _ => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// Answers requests made with a rejected access token with a 401.
+async fn reject_tokens(
+    State(reddit): State<SharedReddit>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let rejected = bearer_token(request.headers()).is_some_and(|token| {
+        let reddit = reddit.lock().unwrap();
+        reddit.rejected_tokens.iter().any(|t| t == token)
+    });
+
+    if rejected {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(next.run(request).await)
+}
+
+fn listing_of(things: impl IntoIterator<Item = Value>, after: Option<String>) -> Json<Value> {
+    let children: Vec<Value> = things
+        .into_iter()
+        .map(|data| {
+            let kind = data["name"].as_str().unwrap_or_default()[..2].to_string();
+            json!({ "kind": kind, "data": data })
+        })
+        .collect();
+
+    Json(json!({
+        "kind": "Listing",
+        "data": { "children": children, "after": after, "before": null },
+    }))
+}
+
+async fn access_token(State(reddit): State<SharedReddit>) -> Json<Value> {
+    let mut reddit = reddit.lock().unwrap();
+    reddit.token_requests += 1;
+
+    let token = format!("{ACCESS_TOKEN}-{}", reddit.token_requests);
+    if reddit.reject_first_token && reddit.token_requests == 1 {
+        reddit.rejected_tokens.push(token.clone());
+    }
+
+    Json(json!({
+        "access_token": token,
+        "token_type": "bearer",
+        "expires_in": reddit.token_lifetime.unwrap_or(86400),
+        "scope": "*",
+    }))
+}
+
+async fn user_listing(
+    State(reddit): State<SharedReddit>,
+    Path((_username, listing)): Path<(String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    let name = match query.get("type") {
+        Some(kind) => format!("{listing}?type={kind}"),
+        None => listing,
+    };
+
+    Ok(page(&reddit, &name, &query))
+}
+
+async fn message_listing(
+    State(reddit): State<SharedReddit>,
+    Path(listing): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    Ok(page(&reddit, &format!("message/{listing}"), &query))
+}
+
+async fn subreddit_listing(
+    State(reddit): State<SharedReddit>,
+    Path(listing): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    Ok(page(&reddit, &format!("subreddits/mine/{listing}"), &query))
+}
+
+/// Serves the page of a listing that follows the `after` cursor.
+fn page(reddit: &SharedReddit, name: &str, query: &HashMap<String, String>) -> Json<Value> {
+    let reddit = reddit.lock().unwrap();
+    let things = reddit.listings.get(name).cloned().unwrap_or_default();
+
+    let start = match query.get("after") {
+        Some(after) => things
+            .iter()
+            .position(|t| t["name"] == after.as_str())
+            .map_or(things.len(), |i| i + 1),
+        None => 0,
+    };
+    let page: Vec<Value> = things.into_iter().skip(start).take(PAGE_SIZE + 1).collect();
+
+    let after =
+        (page.len() > PAGE_SIZE).then(|| page[PAGE_SIZE - 1]["name"].as_str().unwrap().to_string());
+
+    listing_of(page.into_iter().take(PAGE_SIZE), after)
+}
+
+#[derive(Deserialize)]
+struct InfoQuery {
+    id: String,
+}
+
+async fn info(
+    State(reddit): State<SharedReddit>,
+    Query(query): Query<InfoQuery>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    let mut reddit = reddit.lock().unwrap();
+    let fullnames: Vec<&str> = query.id.split(',').collect();
+    // Reddit looks up at most 100 things at once.
+    if fullnames.len() > 100 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    reddit.info_requests.push(fullnames.len());
+
+    let things = fullnames.into_iter().filter_map(|fullname| {
+        reddit
+            .listings
+            .values()
+            .flatten()
+            .find(|t| t["name"] == fullname)
+            .cloned()
+    });
+
+    Ok(listing_of(things, None))
+}
+
+#[derive(Deserialize)]
+struct IdForm {
+    id: String,
+}
+
+async fn del(
+    State(reddit): State<SharedReddit>,
+    headers: HeaderMap,
+    Form(form): Form<IdForm>,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    reddit
+        .lock()
+        .unwrap()
+        .mutations
+        .push(Mutation::Del(form.id));
+
+    Ok(Json(json!({})))
+}
+
+#[derive(Deserialize)]
+struct EditForm {
+    thing_id: String,
+    text: String,
+}
+
+async fn edit_usertext(
+    State(reddit): State<SharedReddit>,
+    headers: HeaderMap,
+    Form(form): Form<EditForm>,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    let mut reddit = reddit.lock().unwrap();
+    if reddit.rate_limited_edits > 0 {
+        reddit.rate_limited_edits -= 1;
+
+        return Ok(Json(json!({
+            "jquery": [[0, 1, "call", [".error.RATELIMIT.field-ratelimit"]]],
+            "success": false,
+        })));
+    }
+
+    reddit.mutations.push(Mutation::Edit {
+        thing_id: form.thing_id,
+        text: form.text,
+    });
+
+    Ok(Json(json!({
+        "jquery": [[0, 1, "call", ["body"]], [1, 2, "attr", "find"]],
+        "success": true,
+    })))
+}
+
+async fn unsave(
+    State(reddit): State<SharedReddit>,
+    headers: HeaderMap,
+    Form(form): Form<IdForm>,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    reddit
+        .lock()
+        .unwrap()
+        .mutations
+        .push(Mutation::Unsave(form.id));
+
+    Ok(Json(json!({})))
+}
+
+async fn friends(
+    State(reddit): State<SharedReddit>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    let reddit = reddit.lock().unwrap();
+    let friends = reddit.listings.get("friends").cloned().unwrap_or_default();
+
+    Ok(Json(json!({
+        "kind": "UserList",
+        "data": { "children": friends },
+    })))
+}
+
+async fn unfriend(
+    State(reddit): State<SharedReddit>,
+    Path(username): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    authorize(&headers)?;
+
+    reddit
+        .lock()
+        .unwrap()
+        .mutations
+        .push(Mutation::Unfriend(username));
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn unhide(
+    State(reddit): State<SharedReddit>,
+    headers: HeaderMap,
+    Form(form): Form<IdForm>,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    reddit
+        .lock()
+        .unwrap()
+        .mutations
+        .push(Mutation::Unhide(form.id));
+
+    Ok(Json(json!({})))
+}
+
+#[derive(Deserialize)]
+struct VoteForm {
+    id: String,
+    dir: i8,
+}
+
+async fn vote(
+    State(reddit): State<SharedReddit>,
+    headers: HeaderMap,
+    Form(form): Form<VoteForm>,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    reddit.lock().unwrap().mutations.push(Mutation::Vote {
+        id: form.id,
+        dir: form.dir,
+    });
+
+    Ok(Json(json!({})))
+}
+
+async fn del_msg(
+    State(reddit): State<SharedReddit>,
+    headers: HeaderMap,
+    Form(form): Form<IdForm>,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    reddit
+        .lock()
+        .unwrap()
+        .mutations
+        .push(Mutation::DelMsg(form.id));
+
+    Ok(Json(json!({})))
+}
+
+#[derive(Deserialize)]
+struct SubscribeForm {
+    action: String,
+    sr_name: String,
+}
+
+async fn subscribe(
+    State(reddit): State<SharedReddit>,
+    headers: HeaderMap,
+    Form(form): Form<SubscribeForm>,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&headers)?;
+
+    if form.action != "unsub" {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    reddit
+        .lock()
+        .unwrap()
+        .mutations
+        .push(Mutation::Unsubscribe(form.sr_name));
+
+    Ok(Json(json!({})))
+}
@@ -0,0 +1,524 @@
+mod fake_reddit;
+
+use fake_reddit::{FakeReddit, Mutation, comment, link_post, post, run};
+use std::{fs, io::Write, path::PathBuf};
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+const COMMENTS_CSV: &str = "\
+id,permalink,date,ip,subreddit,gildings,link,parent,body,media
+c1,https://www.reddit.com/r/rust/comments/abc/post/c1/,2023-05-14 17:23:51+00:00[UTC],,rust,0,https://www.reddit.com/r/rust/comments/abc/post/,,first,
+c2,https://www.reddit.com/r/rust/comments/abc/post/c2/,2023-05-15 09:01:02+00:00[UTC],,rust,0,https://www.reddit.com/r/rust/comments/abc/post/,,second,
+";
+
+/// A fresh directory for a test's export files.
+fn export_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("shreddit-{test}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn expected_mutations() -> Vec<Mutation> {
+    ["t1_c1", "t1_c2"]
+        .into_iter()
+        .flat_map(|fullname| {
+            [
+                Mutation::Edit {
+                    thing_id: fullname.to_string(),
+                    text: "[removed]".to_string(),
+                },
+                Mutation::Del(fullname.to_string()),
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn reads_an_extracted_export() {
+    let dir = export_dir("extracted");
+    fs::write(dir.join("comments.csv"), COMMENTS_CSV).unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(reddit.mutations(), expected_mutations());
+}
+
+#[test]
+fn reads_a_zipped_export() {
+    let path = export_dir("zipped").join("export.zip");
+
+    let mut zip = ZipWriter::new(fs::File::create(&path).unwrap());
+    zip.start_file("comments.csv", SimpleFileOptions::default())
+        .unwrap();
+    zip.write_all(COMMENTS_CSV.as_bytes()).unwrap();
+    zip.finish().unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--gdpr-export-dir")
+        .arg(&path));
+
+    assert_eq!(reddit.mutations(), expected_mutations());
+}
+
+#[test]
+fn missing_files_are_skipped() {
+    let dir = export_dir("missing");
+    fs::write(dir.join("comments.csv"), COMMENTS_CSV).unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    let output = run(reddit
+        .shreddit()
+        .args(["--thing-types", "saved-comments,comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(reddit.mutations(), expected_mutations());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("saved_comments.csv"));
+}
+
+const MALFORMED_COMMENTS_CSV: &str = "\
+id,permalink,date,ip,subreddit,gildings,link,parent,body,media
+c1,https://www.reddit.com/r/rust/comments/abc/post/c1/,2023-05-14 17:23:51+00:00[UTC],,rust,0,https://www.reddit.com/r/rust/comments/abc/post/,,first,
+c3,https://www.reddit.com/r/rust/comments/abc/post/c3/,yesterday,,rust,0,https://www.reddit.com/r/rust/comments/abc/post/,,third,
+c2,https://www.reddit.com/r/rust/comments/abc/post/c2/,2023-05-15 09:01:02+00:00[UTC],,rust,0,https://www.reddit.com/r/rust/comments/abc/post/,,second,
+";
+
+#[test]
+fn malformed_rows_are_skipped_and_summarized() {
+    let dir = export_dir("malformed");
+    fs::write(dir.join("comments.csv"), MALFORMED_COMMENTS_CSV).unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    let output = run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(reddit.mutations(), expected_mutations());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("comments.csv line 3"));
+    assert!(stdout.contains("Skipped 1 malformed row(s) of comments.csv"));
+}
+
+#[test]
+fn strict_mode_stops_at_the_first_malformed_row() {
+    let dir = export_dir("strict");
+    fs::write(dir.join("comments.csv"), MALFORMED_COMMENTS_CSV).unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    let output = reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--gdpr-strict")
+        .arg("--gdpr-export-dir")
+        .arg(&dir)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(reddit.mutations(), expected_mutations()[..2]);
+}
+
+#[test]
+fn missing_columns_are_reported_once() {
+    let dir = export_dir("columns");
+    fs::write(
+        dir.join("comments.csv"),
+        "id,permalink,date,subreddit,text\nc1,/r/rust/c1,2023-05-14 17:23:51+00:00[UTC],rust,first\n",
+    )
+    .unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    let output = run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(reddit.mutations(), []);
+    assert!(stdout.contains("Skipped comments.csv: missing columns: body"));
+    assert!(!stdout.contains("malformed row"));
+}
+
+#[test]
+fn clears_post_and_comment_votes() {
+    let dir = export_dir("votes");
+    fs::write(
+        dir.join("post_votes.csv"),
+        "id,permalink,direction\n\
+         p1,https://www.reddit.com/r/rust/comments/p1/post/,up\n\
+         p2,https://www.reddit.com/r/work/comments/p2/post/,down\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("comment_votes.csv"),
+        "id,permalink,direction\n\
+         c1,https://www.reddit.com/r/rust/comments/p1/post/c1/,up\n",
+    )
+    .unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "votes"])
+        .args(["--skip-subreddits", "work"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    let clear_vote = |id: &str| Mutation::Vote {
+        id: id.to_string(),
+        dir: 0,
+    };
+    assert_eq!(
+        reddit.mutations(),
+        [clear_vote("t3_p1"), clear_vote("t1_c1")]
+    );
+}
+
+#[test]
+fn deletes_messages() {
+    let dir = export_dir("messages");
+    fs::write(
+        dir.join("messages.csv"),
+        "id,permalink,thread_id,date,ip,from,to,subject,body\n\
+         m1,https://www.reddit.com/message/messages/m1,,2023-05-14 17:23:51+00:00[UTC],,someone,me,Hi,Hello\n\
+         m2,https://www.reddit.com/message/messages/m2,,2023-05-14 17:23:51+00:00[UTC],,me,friend,Hi,Hello\n",
+    )
+    .unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "messages"])
+        .args(["--skip-message-users", "friend"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(reddit.mutations(), [Mutation::DelMsg("t4_m1".to_string())]);
+}
+
+#[test]
+fn unsubscribes_from_subreddits() {
+    let dir = export_dir("subscriptions");
+    fs::write(
+        dir.join("subscribed_subreddits.csv"),
+        "subreddit\nrust\nwork\n",
+    )
+    .unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "subscriptions"])
+        .args(["--only-subreddits", "rust"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(
+        reddit.mutations(),
+        [Mutation::Unsubscribe("rust".to_string())]
+    );
+}
+
+#[test]
+fn unhides_hidden_posts() {
+    let dir = export_dir("hidden");
+    fs::write(
+        dir.join("hidden_posts.csv"),
+        "id,permalink\n\
+         p1,https://www.reddit.com/r/rust/comments/p1/post/\n\
+         p2,https://www.reddit.com/r/work/comments/p2/post/\n",
+    )
+    .unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "hidden-posts"])
+        .args(["--skip-subreddits", "work"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(reddit.mutations(), [Mutation::Unhide("t3_p1".to_string())]);
+}
+
+#[test]
+fn overwrites_self_posts_looked_up_through_the_api() {
+    let dir = export_dir("posts");
+    fs::write(
+        dir.join("posts.csv"),
+        "id,permalink,date,ip,subreddit,gildings,title,url,body\n\
+         p1,https://www.reddit.com/r/rust/comments/p1/post/,2023-05-14 17:23:51+00:00[UTC],,rust,0,Post p1,,text\n\
+         p2,https://www.reddit.com/r/rust/comments/p2/post/,2023-05-14 17:23:51+00:00[UTC],,rust,0,Post p2,https://example.com/,\n",
+    )
+    .unwrap();
+
+    let reddit = FakeReddit::new()
+        .posts([post("p1", "rust", "text"), link_post("p2", "rust")])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "posts"])
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(
+        reddit.mutations(),
+        [
+            Mutation::Edit {
+                thing_id: "t3_p1".to_string(),
+                text: "[removed]".to_string(),
+            },
+            Mutation::Del("t3_p1".to_string()),
+            Mutation::Del("t3_p2".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn keep_gilded_reads_the_gildings_column() {
+    let dir = export_dir("gilded");
+    fs::write(
+        dir.join("comments.csv"),
+        "id,permalink,date,ip,subreddit,gildings,link,parent,body,media\n\
+         c1,/r/rust/comments/abc/post/c1/,2023-05-14 17:23:51+00:00[UTC],,rust,0,,,first,\n\
+         c2,/r/rust/comments/abc/post/c2/,2023-05-14 17:23:51+00:00[UTC],,rust,2,,,second,\n\
+         c3,/r/rust/comments/abc/post/c3/,2023-05-14 17:23:51+00:00[UTC],,rust,,,,third,\n",
+    )
+    .unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--keep-gilded")
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    let shredded: Vec<Mutation> = ["t1_c1", "t1_c3"]
+        .into_iter()
+        .flat_map(|fullname| {
+            [
+                Mutation::Edit {
+                    thing_id: fullname.to_string(),
+                    text: "[removed]".to_string(),
+                },
+                Mutation::Del(fullname.to_string()),
+            ]
+        })
+        .collect();
+    assert_eq!(reddit.mutations(), shredded);
+}
+
+#[test]
+fn keep_distinguished_looks_up_gdpr_things() {
+    let dir = export_dir("distinguished");
+    fs::write(
+        dir.join("comments.csv"),
+        "id,permalink,date,ip,subreddit,gildings,link,parent,body,media\n\
+         c1,/r/rust/comments/abc/post/c1/,2023-05-14 17:23:51+00:00[UTC],,rust,0,,,first,\n\
+         c2,/r/rust/comments/abc/post/c2/,2023-05-14 17:23:51+00:00[UTC],,rust,0,,,second,\n\
+         c3,/r/rust/comments/abc/post/c3/,2023-05-14 17:23:51+00:00[UTC],,rust,0,,,third,\n",
+    )
+    .unwrap();
+
+    let mut distinguished = comment("c2", "rust", "second");
+    distinguished["distinguished"] = "moderator".into();
+
+    // c3 isn't known to the API, so it's kept rather than risk deleting a distinguished comment.
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "first"), distinguished])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--keep-distinguished")
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(
+        reddit.mutations(),
+        [
+            Mutation::Edit {
+                thing_id: "t1_c1".to_string(),
+                text: "[removed]".to_string(),
+            },
+            Mutation::Del("t1_c1".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn max_score_uses_scores_from_the_api() {
+    let dir = export_dir("max-score");
+    fs::write(
+        dir.join("comments.csv"),
+        "id,permalink,date,ip,subreddit,gildings,link,parent,body,media\n\
+         c1,/r/rust/comments/abc/post/c1/,2023-05-14 17:23:51+00:00[UTC],,rust,0,,,first,\n\
+         c2,/r/rust/comments/abc/post/c2/,2023-05-14 17:23:51+00:00[UTC],,rust,0,,,second,\n",
+    )
+    .unwrap();
+
+    let mut popular = comment("c2", "rust", "second");
+    popular["score"] = 100.into();
+
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "first"), popular])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .args(["--max-score", "10"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(
+        reddit.mutations(),
+        [
+            Mutation::Edit {
+                thing_id: "t1_c1".to_string(),
+                text: "[removed]".to_string(),
+            },
+            Mutation::Del("t1_c1".to_string()),
+        ]
+    );
+    assert_eq!(reddit.info_requests(), [2]);
+}
+
+#[test]
+fn things_are_looked_up_in_batches_of_100() {
+    let dir = export_dir("batches");
+    let rows: String = (0..150)
+        .map(|i| {
+            format!("c{i},/r/rust/comments/abc/post/c{i}/,2023-05-14 17:23:51+00:00[UTC],,rust,0,,,body,\n")
+        })
+        .collect();
+    fs::write(
+        dir.join("comments.csv"),
+        format!("id,permalink,date,ip,subreddit,gildings,link,parent,body,media\n{rows}"),
+    )
+    .unwrap();
+
+    let reddit = FakeReddit::new().start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .arg("--dry-run")
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(reddit.info_requests(), [100, 50]);
+}
+
+#[test]
+fn skips_deleted_things_and_only_deletes_overwritten_ones() {
+    let dir = export_dir("already-shredded");
+    fs::write(
+        dir.join("comments.csv"),
+        "id,permalink,date,ip,subreddit,gildings,link,parent,body,media\n\
+         c1,/r/rust/comments/abc/post/c1/,2023-05-14 17:23:51+00:00[UTC],,rust,0,,,first,\n\
+         c2,/r/rust/comments/abc/post/c2/,2023-05-14 17:23:51+00:00[UTC],,rust,0,,,second,\n\
+         c3,/r/rust/comments/abc/post/c3/,2023-05-14 17:23:51+00:00[UTC],,rust,0,,,[removed],\n",
+    )
+    .unwrap();
+
+    let mut deleted = comment("c2", "rust", "[deleted]");
+    deleted["author"] = "[deleted]".into();
+
+    let reddit = FakeReddit::new()
+        .comments([comment("c1", "rust", "first"), deleted])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(
+        reddit.mutations(),
+        [
+            Mutation::Edit {
+                thing_id: "t1_c1".to_string(),
+                text: "[removed]".to_string(),
+            },
+            Mutation::Del("t1_c1".to_string()),
+            Mutation::Del("t1_c3".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn before_uses_the_exact_creation_time_from_the_api() {
+    let dir = export_dir("exact-created");
+    fs::write(
+        dir.join("comments.csv"),
+        "id,permalink,date,ip,subreddit,gildings,link,parent,body,media\n\
+         c1,/r/rust/comments/abc/post/c1/,2023-11-14 22:14:23+00:00[UTC],,rust,0,,,first,\n\
+         c2,/r/rust/comments/abc/post/c2/,2023-11-14 22:13:20+00:00[UTC],,rust,0,,,second,\n",
+    )
+    .unwrap();
+
+    // 63s after a multiple of 128s, which an `f32` would round down to before the cutoff.
+    let mut recent = comment("c1", "rust", "first");
+    recent["created_utc"] = 1_700_000_063.0.into();
+
+    let reddit = FakeReddit::new()
+        .comments([recent, comment("c2", "rust", "second")])
+        .start();
+
+    run(reddit
+        .shreddit()
+        .args(["--thing-types", "comments"])
+        .args(["--replacement-comment", "[removed]"])
+        .args(["--before", "2023-11-14T22:13:50Z"])
+        .arg("--gdpr-export-dir")
+        .arg(&dir));
+
+    assert_eq!(
+        reddit.mutations(),
+        [
+            Mutation::Edit {
+                thing_id: "t1_c2".to_string(),
+                text: "[removed]".to_string(),
+            },
+            Mutation::Del("t1_c2".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,48 @@
+mod fake_reddit;
+
+use fake_reddit::{FakeReddit, Mutation, USERNAME, comment};
+use futures_util::{StreamExt, pin_mut};
+use shreddit::{Outcome, Shredder, ThingType};
+
+#[tokio::test]
+async fn run_reports_an_event_per_thing() {
+    let reddit = FakeReddit::new()
+        .comments([
+            comment("c1", "rust", "first"),
+            comment("c2", "work", "second"),
+        ])
+        .start();
+
+    let shredder = Shredder::builder(USERNAME, "hunter2", "client-id", "client-secret")
+        .api_url(reddit.url())
+        .auth_url(reddit.url())
+        .thing_types([ThingType::Comments])
+        .skip_subreddits(["work"])
+        .edit_only(true)
+        .build();
+
+    shredder.authenticate().await.unwrap();
+
+    let events = shredder.run().unwrap();
+    pin_mut!(events);
+
+    let mut outcomes = Vec::new();
+    while let Some(event) = events.next().await {
+        outcomes.push((event.name, event.outcome.unwrap()));
+    }
+
+    assert_eq!(
+        outcomes,
+        [
+            ("t1_c1".to_string(), Outcome::Edited),
+            ("t1_c2".to_string(), Outcome::Skipped),
+        ]
+    );
+    assert_eq!(
+        reddit.mutations(),
+        [Mutation::Edit {
+            thing_id: "t1_c1".to_string(),
+            text: shreddit::things::LOREM_IPSUM.to_string(),
+        }]
+    );
+}